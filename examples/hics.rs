@@ -174,10 +174,7 @@ struct Hics {
 impl<'a> control::System<'a, HeatIndexSpace> for Hics {
     fn meter(&self) -> StreamProcessor<'a, (), HeatIndexSpace> {
         fn read_out<'a, X: 'a + Copy>(finger: Arc<Mutex<X>>) -> StreamProcessor<'a, (), X> {
-            StreamProcessor::Put(
-                *Arc::clone(&finger).lock().unwrap(),
-                Box::new(|| read_out(finger)),
-            )
+            StreamProcessor::put(*Arc::clone(&finger).lock().unwrap(), || read_out(finger))
         }
 
         read_out(Arc::clone(&self.thermohygrometer_finger))
@@ -243,7 +240,7 @@ fn driver(hics: Hics) {
 
         counter += 1;
 
-        StreamProcessor::Put(counter, Box::new(move || control(hics, counter)))
+        StreamProcessor::put(counter, move || control(hics, counter))
     }
 
     assert!(UNSAFE_BARRIER > SERVICE_BARRIER);
@@ -0,0 +1,337 @@
+//! This module provides bounded model checking of stream processors reading inputs from a finite alphabet.
+//!
+//! A stream processor is a possibly infinite tree branching on the inputs it reads.
+//! If the inputs range over a finite alphabet, that tree can be unfolded systematically up to a certain depth, that is, for all sequences of inputs up to a certain length.
+//! The functions of this module do so to collect the reachable output sequences or to search for a shortest sequence of inputs (a counterexample) leading to a bad output or to an unproductive stream processor.
+//!
+//! As stream processors are consumed by reading, the functions expect a closure constructing the stream processor to be analyzed and replay every sequence of inputs on a fresh one.
+//! Hence the analysis takes time exponential in the depth and should only be used for small alphabets and depths.
+//! Moreover, note that the analysis does not return if the stream processor writes infinitely many outputs without reading.
+
+use super::closures::{Apply, Force};
+use super::StreamProcessor;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// [`Trace<A, B>`] is a sequence of inputs of type `A` together with the outputs of type `B` a stream processor writes on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace<A, B> {
+    /// inputs read by the stream processor
+    pub inputs: Vec<A>,
+    /// outputs written by the stream processor up to the point where it needs more input (or terminates)
+    pub outputs: Vec<B>,
+}
+
+/// [`Run<B>`] is the result of running a stream processor writing outputs of type `B` on a sequence of inputs.
+struct Run<B> {
+    /// outputs written
+    outputs: Vec<B>,
+    /// number of inputs read since the last output
+    silent: usize,
+    /// whether the stream processor has terminated
+    done: bool,
+}
+
+/// Run a stream processor on a sequence of inputs until it needs more input or terminates.
+/// - `sp` is the stream processor.
+/// - `inputs` are the inputs.
+fn run<'a, A: Clone + 'a, B, R>(mut sp: StreamProcessor<'a, A, B, R>, inputs: &[A]) -> Run<B> {
+    let mut inputs = inputs.iter();
+    let mut outputs = Vec::new();
+    let mut silent = 0;
+
+    loop {
+        match sp {
+            StreamProcessor::Get(f) => match inputs.next() {
+                Some(a) => {
+                    silent += 1;
+                    sp = f.apply(a.clone());
+                }
+                None => {
+                    return Run {
+                        outputs,
+                        silent,
+                        done: false,
+                    }
+                }
+            },
+            StreamProcessor::Put(b, lazy_sp) => {
+                outputs.push(b);
+                silent = 0;
+                sp = lazy_sp.force();
+            }
+            StreamProcessor::Done(_) => {
+                return Run {
+                    outputs,
+                    silent,
+                    done: true,
+                }
+            }
+        }
+    }
+}
+
+/// Unfold a stream processor breadth-first over all sequences of inputs up to a certain length.
+/// - `sp` is the closure constructing the stream processor.
+/// - `alphabet` are the possible inputs.
+/// - `depth` is the maximal length of the sequences of inputs.
+/// - `visit` is called with every sequence of inputs and the respective run and stops the unfolding by returning `true`.
+///
+/// The sequences of inputs are visited ordered by length and sequences on which the stream processor terminates are not extended.
+fn unfold<'a, A, B, R, F, V>(sp: F, alphabet: &[A], depth: usize, mut visit: V)
+where
+    A: Clone + 'a,
+    F: Fn() -> StreamProcessor<'a, A, B, R>,
+    V: FnMut(&[A], Run<B>) -> bool,
+{
+    let mut frontier = VecDeque::from([Vec::new()]);
+
+    while let Some(inputs) = frontier.pop_front() {
+        let run = run(sp(), &inputs);
+        let done = run.done;
+
+        if visit(&inputs, run) {
+            return;
+        }
+
+        if !done && inputs.len() < depth {
+            for a in alphabet {
+                let mut extended = inputs.clone();
+                extended.push(a.clone());
+                frontier.push_back(extended);
+            }
+        }
+    }
+}
+
+/// Collect the output sequences a stream processor can write on reading at most a certain number of inputs.
+/// - `sp` is the closure constructing the stream processor.
+/// - `alphabet` are the possible inputs.
+/// - `depth` is the maximal number of inputs.
+///
+/// The output sequences are ordered by the length of the shortest sequence of inputs reaching them and contain no duplicates.
+///
+/// # Examples
+///
+/// Listing the outputs of negating a stream of bools:
+///
+/// ```
+/// use rspl::analysis::reachable_outputs;
+/// use rspl::combinators::map;
+/// use rspl::StreamProcessor;
+///
+/// let negate = || -> StreamProcessor<_, _> { map(|b: bool| !b) };
+///
+/// let outputs = reachable_outputs(negate, &[false, true], 1);
+///
+/// assert_eq!(outputs, vec![vec![], vec![true], vec![false]]);
+/// ```
+pub fn reachable_outputs<'a, A, B, R, F>(sp: F, alphabet: &[A], depth: usize) -> Vec<Vec<B>>
+where
+    A: Clone + 'a,
+    B: PartialEq,
+    F: Fn() -> StreamProcessor<'a, A, B, R>,
+{
+    let mut reachable = Vec::new();
+
+    unfold(sp, alphabet, depth, |_, run| {
+        if !reachable.contains(&run.outputs) {
+            reachable.push(run.outputs);
+        }
+        false
+    });
+
+    reachable
+}
+
+/// Search for a shortest sequence of inputs of at most a certain length on which a stream processor writes a bad output.
+/// - `sp` is the closure constructing the stream processor.
+/// - `alphabet` are the possible inputs.
+/// - `depth` is the maximal length of the sequence of inputs.
+/// - `bad` is the predicate deciding whether an output is bad.
+///
+/// The result is `None` if there is no such sequence.
+///
+/// # Examples
+///
+/// Finding out how a counter can reach three:
+///
+/// ```
+/// use rspl::analysis::find_bad_output;
+/// use rspl::combinators::scan;
+/// use rspl::StreamProcessor;
+///
+/// let counter = || -> StreamProcessor<_, _> { scan(0, |n: usize, step: usize| n + step) };
+///
+/// let trace = find_bad_output(counter, &[1, 2], 5, |n| *n == 3).unwrap();
+///
+/// assert_eq!(trace.inputs, vec![1, 2]);
+/// assert_eq!(trace.outputs, vec![1, 3]);
+/// ```
+pub fn find_bad_output<'a, A, B, R, F, P>(
+    sp: F,
+    alphabet: &[A],
+    depth: usize,
+    bad: P,
+) -> Option<Trace<A, B>>
+where
+    A: Clone + 'a,
+    F: Fn() -> StreamProcessor<'a, A, B, R>,
+    P: Fn(&B) -> bool,
+{
+    let mut counterexample = None;
+
+    unfold(sp, alphabet, depth, |inputs, run| {
+        if run.outputs.iter().any(&bad) {
+            counterexample = Some(Trace {
+                inputs: inputs.to_vec(),
+                outputs: run.outputs,
+            });
+            return true;
+        }
+        false
+    });
+
+    counterexample
+}
+
+/// Search for a shortest sequence of inputs of at most a certain length after which a stream processor has read a certain number of inputs in a row without writing anything.
+/// - `sp` is the closure constructing the stream processor.
+/// - `alphabet` are the possible inputs.
+/// - `depth` is the maximal length of the sequence of inputs.
+/// - `gets` is the number of inputs in a row considered unproductive.
+///
+/// The result is `None` if there is no such sequence.
+/// Note that stream processors which have terminated are not considered unproductive.
+///
+/// # Examples
+///
+/// Finding out how a filter can swallow two inputs in a row:
+///
+/// ```
+/// use rspl::analysis::find_unproductive;
+/// use rspl::combinators::filter;
+/// use rspl::StreamProcessor;
+///
+/// let trues = || -> StreamProcessor<_, _> { filter(|b: &bool| *b) };
+///
+/// let trace = find_unproductive(trues, &[true, false], 3, 2).unwrap();
+///
+/// assert_eq!(trace.inputs, vec![false, false]);
+/// assert!(trace.outputs.is_empty());
+/// ```
+pub fn find_unproductive<'a, A, B, R, F>(
+    sp: F,
+    alphabet: &[A],
+    depth: usize,
+    gets: usize,
+) -> Option<Trace<A, B>>
+where
+    A: Clone + 'a,
+    F: Fn() -> StreamProcessor<'a, A, B, R>,
+{
+    let mut counterexample = None;
+
+    unfold(sp, alphabet, depth, |inputs, run| {
+        if !run.done && run.silent >= gets {
+            counterexample = Some(Trace {
+                inputs: inputs.to_vec(),
+                outputs: run.outputs,
+            });
+            return true;
+        }
+        false
+    });
+
+    counterexample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinators::{filter, map, take};
+
+    use alloc::vec;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Event {
+        Arm,
+        Disarm,
+        Fire,
+    }
+
+    // This stream processor fires a missile only if it is armed but forgets to disarm when firing.
+    fn launcher<'a>(armed: bool) -> StreamProcessor<'a, Event, &'static str> {
+        StreamProcessor::get(move |event| match event {
+            Event::Arm => launcher(true),
+            Event::Disarm => launcher(false),
+            Event::Fire if armed => StreamProcessor::put("launch", move || launcher(armed)),
+            Event::Fire => StreamProcessor::put("click", move || launcher(armed)),
+        })
+    }
+
+    const EVENTS: [Event; 3] = [Event::Arm, Event::Disarm, Event::Fire];
+
+    #[test]
+    fn test_find_bad_output() {
+        let trace = find_bad_output(|| launcher(false), &EVENTS, 4, |output| *output == "launch");
+        assert_eq!(
+            trace,
+            Some(Trace {
+                inputs: vec![Event::Arm, Event::Fire],
+                outputs: vec!["launch"],
+            })
+        );
+
+        let trace = find_bad_output(|| launcher(false), &EVENTS[1..], 4, |o| *o == "launch");
+        assert_eq!(trace, None);
+    }
+
+    #[test]
+    fn test_find_unproductive() {
+        let trace = find_unproductive(|| launcher(false), &EVENTS, 4, 3);
+        assert_eq!(
+            trace,
+            Some(Trace {
+                inputs: vec![Event::Arm, Event::Arm, Event::Arm],
+                outputs: vec![],
+            })
+        );
+
+        assert_eq!(
+            find_unproductive(
+                || -> StreamProcessor<_, _> { map(|n: usize| n) },
+                &[0, 1],
+                4,
+                1
+            ),
+            None
+        );
+        assert_eq!(find_unproductive(|| take(0), &[0], 4, 0), None);
+    }
+
+    #[test]
+    fn test_reachable_outputs() {
+        let outputs = reachable_outputs(|| launcher(false), &EVENTS, 2);
+        assert_eq!(
+            outputs,
+            vec![
+                vec![],
+                vec!["click"],
+                vec!["launch"],
+                vec!["click", "click"]
+            ]
+        );
+
+        let outputs = reachable_outputs(
+            || -> StreamProcessor<_, _> { filter(|n: &usize| *n > 0) },
+            &[0, 1],
+            2,
+        );
+        assert_eq!(outputs, vec![vec![], vec![1], vec![1, 1]]);
+
+        let outputs = reachable_outputs(|| take(1), &[0, 1], 5);
+        assert_eq!(outputs, vec![vec![], vec![0], vec![1]]);
+    }
+}
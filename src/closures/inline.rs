@@ -0,0 +1,189 @@
+//! This module provides the container storing small closures inline which backs the `inline-closures`-feature.
+//!
+//! It is the only module containing `unsafe`-code.
+
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr;
+
+/// The number of machine words an [`InlineFnOnce`] can store inline (which is enough for four captured references).
+const INLINE_WORDS: usize = 4;
+
+/// [`VTable`] collects what is needed to handle a stored closure whose type is erased.
+struct VTable {
+    /// function moving the closure out of the storage and calling it with the argument read from the second pointer to write the result to the third one
+    call: unsafe fn(*mut u8, *mut u8, *mut u8),
+    /// function dropping the closure in the storage
+    drop: unsafe fn(*mut u8),
+    /// whether the closure itself is stored inline
+    inline: bool,
+}
+
+/// [`Stored<A, T, G, INLINE>`] provides the vtable of closures of type `G` from `A` to `T` where `INLINE` is whether `G` is the actual closure rather than its `Box`.
+struct Stored<A, T, G, const INLINE: bool>(PhantomData<fn(A) -> T>, PhantomData<G>);
+
+impl<A, T, G, const INLINE: bool> Stored<A, T, G, INLINE>
+where
+    G: FnOnce(A) -> T,
+{
+    /// the vtable shared by all stored closures of type `G`
+    const VTABLE: VTable = VTable {
+        call: call_stored::<A, T, G>,
+        drop: drop_stored::<G>,
+        inline: INLINE,
+    };
+}
+
+/// [`InlineFnOnce<A, T>`] stores a closure from `A` to `T` inline if it fits into four machine words (and is not aligned stricter than a machine word) and in a `Box` otherwise.
+/// In the latter case only the pointer to the heap is stored inline.
+///
+/// Note that closures not capturing anything or only a few small values - like the ones of [`map`](`crate::combinators::map`) or hand-written state machines usually do - fit, while closures capturing whole stream processors - like the ones of [`compose`](`crate::combinators::compose`) do - do not.
+pub(super) struct InlineFnOnce<'a, A, T> {
+    /// the type of the stored closure is erased but its lifetime and signature are kept
+    phantom: PhantomData<Box<dyn FnOnce(A) -> T + 'a>>,
+    /// memory holding the closure or the `Box` of the closure
+    storage: MaybeUninit<[usize; INLINE_WORDS]>,
+    /// vtable of the type of the closure in the storage
+    vtable: &'static VTable,
+}
+
+impl<'a, A, T> InlineFnOnce<'a, A, T> {
+    /// Store a closure inline if it fits and on the heap otherwise.
+    /// - `f` is the closure to be stored.
+    pub(super) fn new<F>(f: F) -> Self
+    where
+        F: FnOnce(A) -> T + 'a,
+    {
+        if Self::fits::<F>() {
+            Self::store(f, &Stored::<A, T, F, true>::VTABLE)
+        } else {
+            Self::store(Box::new(f), &Stored::<A, T, Box<F>, false>::VTABLE)
+        }
+    }
+
+    /// Return whether the closure is stored inline rather than on the heap.
+    pub(super) const fn is_inline(&self) -> bool {
+        self.vtable.inline
+    }
+
+    /// Move the closure out of the storage and call it.
+    /// - `a` is the argument of the call.
+    pub(super) fn call(self, a: A) -> T {
+        // The closure and the argument are moved out by the call, so they must not be dropped again.
+        let mut this = ManuallyDrop::new(self);
+        let mut a = ManuallyDrop::new(a);
+        let mut t = MaybeUninit::<T>::uninit();
+        // SAFETY: The storage holds the closure the vtable was made for, the argument is an `A`, the result is a `T` and `this` is not used afterwards.
+        unsafe {
+            (this.vtable.call)(
+                this.storage.as_mut_ptr().cast(),
+                ptr::addr_of_mut!(*a).cast(),
+                t.as_mut_ptr().cast(),
+            );
+            t.assume_init()
+        }
+    }
+
+    /// Check whether closures of type `G` fit into the storage.
+    const fn fits<G>() -> bool {
+        mem::size_of::<G>() <= mem::size_of::<[usize; INLINE_WORDS]>()
+            && mem::align_of::<G>() <= mem::align_of::<[usize; INLINE_WORDS]>()
+    }
+
+    /// Move a closure which fits into the storage.
+    /// - `g` is the closure.
+    /// - `vtable` is the vtable of the type of `g`.
+    fn store<G>(g: G, vtable: &'static VTable) -> Self
+    where
+        G: FnOnce(A) -> T + 'a,
+    {
+        assert!(Self::fits::<G>());
+
+        let mut storage = MaybeUninit::<[usize; INLINE_WORDS]>::uninit();
+        // SAFETY: `G` fits into the storage w.r.t. both size and alignment as asserted above.
+        unsafe { ptr::write(storage.as_mut_ptr().cast::<G>(), g) };
+
+        Self {
+            phantom: PhantomData,
+            storage,
+            vtable,
+        }
+    }
+}
+
+/// Move a closure of type `G` out of some storage and call it.
+/// - `storage` points to the closure.
+/// - `a` points to the argument of the call.
+/// - `t` points to where the result of the call is written to.
+///
+/// # Safety
+///
+/// `storage` must point to a valid `G` and `a` to a valid `A` which are not used afterwards.
+/// `t` must point to memory valid for writing a `T`.
+unsafe fn call_stored<A, T, G>(storage: *mut u8, a: *mut u8, t: *mut u8)
+where
+    G: FnOnce(A) -> T,
+{
+    // SAFETY: The caller guarantees that the reads are valid and not duplicated.
+    let (g, a) = unsafe { (ptr::read(storage.cast::<G>()), ptr::read(a.cast::<A>())) };
+    // SAFETY: The caller guarantees that the write is valid.
+    unsafe { ptr::write(t.cast::<T>(), g(a)) };
+}
+
+/// Drop a closure of type `G` in some storage.
+/// - `storage` points to the closure.
+///
+/// # Safety
+///
+/// `storage` must point to a valid `G` which is not used afterwards.
+unsafe fn drop_stored<G>(storage: *mut u8) {
+    // SAFETY: The caller guarantees that the closure is valid and not dropped twice.
+    unsafe { ptr::drop_in_place(storage.cast::<G>()) };
+}
+
+impl<A, T> Drop for InlineFnOnce<'_, A, T> {
+    /// Drop the closure which has not been called.
+    fn drop(&mut self) {
+        // SAFETY: The storage holds the closure the vtable was made for and it has not been moved out as `call` prevents dropping.
+        unsafe { (self.vtable.drop)(self.storage.as_mut_ptr().cast()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::rc::Rc;
+
+    #[test]
+    fn test_inline_fn_once() {
+        let small = Rc::new(());
+        let big = [Rc::clone(&small), Rc::clone(&small), Rc::clone(&small)];
+        let big = (big.clone(), big);
+
+        let inline = InlineFnOnce::new({
+            let small = Rc::clone(&small);
+            move |n: usize| Rc::strong_count(&small) + n
+        });
+        let boxed = InlineFnOnce::new(move |n: usize| big.0.len() + big.1.len() + n);
+
+        assert!(inline.is_inline());
+        assert!(!boxed.is_inline());
+        assert_eq!(Rc::strong_count(&small), 8);
+
+        assert_eq!(boxed.call(1), 7);
+        assert_eq!(Rc::strong_count(&small), 2);
+
+        let uncalled = InlineFnOnce::new({
+            let small = Rc::clone(&small);
+            move |()| small
+        });
+        assert_eq!(Rc::strong_count(&small), 3);
+        drop(uncalled);
+        assert_eq!(Rc::strong_count(&small), 2);
+
+        assert_eq!(inline.call(1), 3);
+        assert_eq!(Rc::strong_count(&small), 1);
+    }
+}
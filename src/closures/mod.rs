@@ -0,0 +1,227 @@
+//! This module provides the containers in which stream processors and lazy lists store their closures.
+//!
+//! The containers [`BoxedFn`] and [`BoxedLazy`] are the same types whatever features are enabled.
+//! By default, they hold ordinary `Box`es of trait objects.
+//! With the `inline-closures`-feature enabled, they store small closures inline instead and only fall back to the heap for closures capturing a lot.
+//! Either way, the traits [`Apply`] and [`Force`] allow to call the stored closures.
+
+#[cfg(feature = "inline-closures")]
+#[allow(unsafe_code)]
+mod inline;
+
+#[cfg(not(feature = "inline-closures"))]
+use alloc::boxed::Box;
+
+#[cfg(feature = "inline-closures")]
+use inline::InlineFnOnce;
+
+/// [`Container<A, T>`] is what actually holds a stored closure from `A` to `T`.
+#[cfg(not(feature = "inline-closures"))]
+type Container<'a, A, T> = Box<dyn FnOnce(A) -> T + 'a>;
+
+/// [`Container<A, T>`] is what actually holds a stored closure from `A` to `T`.
+#[cfg(feature = "inline-closures")]
+type Container<'a, A, T> = InlineFnOnce<'a, A, T>;
+
+/// [`BoxedFn<A, T>`] types the stored closures from `A` to `T`.
+pub struct BoxedFn<'a, A, T>(Container<'a, A, T>);
+
+impl<'a, A, T> BoxedFn<'a, A, T> {
+    /// Store a closure.
+    /// - `f` is the closure to be stored.
+    ///
+    /// With the `inline-closures`-feature enabled, `f` is stored inline if it fits into four machine words (and is not aligned stricter than a machine word) and on the heap otherwise.
+    /// Note that closures not capturing anything or only a few small values - like the ones of [`map`](`crate::combinators::map`) or hand-written state machines usually do - fit, while closures capturing whole stream processors - like the ones of [`compose`](`crate::combinators::compose`) do - do not.
+    ///
+    /// # Examples
+    ///
+    /// Storing a closure to call it later:
+    ///
+    /// ```
+    /// use rspl::closures::{Apply, BoxedFn};
+    ///
+    /// let negate = BoxedFn::new(|b: bool| !b);
+    ///
+    /// assert!(!negate.apply(true));
+    /// ```
+    #[inline]
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnOnce(A) -> T + 'a,
+    {
+        #[cfg(not(feature = "inline-closures"))]
+        return Self(Box::new(f));
+        #[cfg(feature = "inline-closures")]
+        return Self(InlineFnOnce::new(f));
+    }
+
+    /// Return whether the closure is stored inline rather than on the heap which it never is without the `inline-closures`-feature.
+    #[inline]
+    pub const fn is_inline(&self) -> bool {
+        #[cfg(not(feature = "inline-closures"))]
+        return false;
+        #[cfg(feature = "inline-closures")]
+        return self.0.is_inline();
+    }
+}
+
+/// [`BoxedLazy<T>`] types the stored thunks of type `T`.
+pub struct BoxedLazy<'a, T>(BoxedFn<'a, (), T>);
+
+impl<'a, T> BoxedLazy<'a, T> {
+    /// Store a thunk.
+    /// - `lazy` is the thunk to be stored.
+    ///
+    /// The thunk is stored like a closure by [`BoxedFn::new`].
+    ///
+    /// # Examples
+    ///
+    /// Storing a thunk to force it later:
+    ///
+    /// ```
+    /// use rspl::closures::{BoxedLazy, Force};
+    ///
+    /// let lazy_true = BoxedLazy::new(|| true);
+    ///
+    /// assert!(lazy_true.force());
+    /// ```
+    #[inline]
+    pub fn new<L>(lazy: L) -> Self
+    where
+        L: FnOnce() -> T + 'a,
+    {
+        Self(BoxedFn::new(move |()| lazy()))
+    }
+
+    /// Return whether the thunk is stored inline rather than on the heap which it never is without the `inline-closures`-feature.
+    #[inline]
+    pub const fn is_inline(&self) -> bool {
+        self.0.is_inline()
+    }
+}
+
+/// Store a closure.
+/// - `f` is the closure to be stored.
+#[inline]
+pub(crate) fn box_fn<'a, A, T, F>(f: F) -> BoxedFn<'a, A, T>
+where
+    F: FnOnce(A) -> T + 'a,
+{
+    BoxedFn::new(f)
+}
+
+/// Store a thunk.
+/// - `lazy` is the thunk to be stored.
+#[inline]
+pub(crate) fn box_lazy<'a, T, L>(lazy: L) -> BoxedLazy<'a, T>
+where
+    L: FnOnce() -> T + 'a,
+{
+    BoxedLazy::new(lazy)
+}
+
+/// A characterization of stored closures from `A` to `T`.
+pub trait Apply<A, T> {
+    /// Call `self` with an argument.
+    fn apply(self, a: A) -> T;
+}
+
+/// A characterization of stored thunks of type `T`.
+pub trait Force<T> {
+    /// Force `self` to its value.
+    fn force(self) -> T;
+}
+
+impl<A, T> Apply<A, T> for BoxedFn<'_, A, T> {
+    /// Call the stored closure.
+    #[inline]
+    fn apply(self, a: A) -> T {
+        #[cfg(not(feature = "inline-closures"))]
+        return (self.0)(a);
+        #[cfg(feature = "inline-closures")]
+        return self.0.call(a);
+    }
+}
+
+impl<T> Force<T> for BoxedLazy<'_, T> {
+    /// Call the stored thunk.
+    #[inline]
+    fn force(self) -> T {
+        self.0.apply(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_fn() {
+        let n = 1;
+        assert_eq!(box_fn(move |m: usize| m + n).apply(1), 2);
+    }
+
+    #[test]
+    fn test_box_lazy() {
+        let n = 1;
+        assert_eq!(box_lazy(move || n + 1).force(), 2);
+    }
+
+    #[test]
+    fn test_payloads() {
+        use crate::streams::finite_lists::FiniteList;
+        use crate::streams::infinite_lists::InfiniteList;
+        use crate::streams::{FiniteStream, Stream};
+        use crate::StreamProcessor;
+
+        let sp = StreamProcessor::Get(BoxedFn::new(|n: usize| {
+            StreamProcessor::Put(n + 1, BoxedLazy::new(|| StreamProcessor::Done(())))
+        }));
+        let inflist = InfiniteList::Cons(1, BoxedLazy::new(|| InfiniteList::constant(2)));
+        let finlist = FiniteList::Cons(1, BoxedLazy::new(|| FiniteList::Nil));
+
+        assert_eq!(*sp.eval_finite(finlist).head().unwrap(), 2);
+        assert_eq!(*inflist.tail().head(), 2);
+    }
+
+    #[test]
+    fn test_size() {
+        use core::mem::size_of;
+
+        #[cfg(not(feature = "inline-closures"))]
+        let words = 2;
+        #[cfg(feature = "inline-closures")]
+        let words = 5;
+
+        assert_eq!(
+            size_of::<BoxedFn<usize, usize>>(),
+            words * size_of::<usize>()
+        );
+        assert_eq!(size_of::<BoxedLazy<usize>>(), words * size_of::<usize>());
+    }
+
+    #[cfg(feature = "inline-closures")]
+    #[test]
+    fn test_inline_map() {
+        use crate::combinators::map;
+        use crate::StreamProcessor;
+
+        let offset = 1;
+
+        let sp: StreamProcessor<_, _> = map(move |n: usize| n + offset);
+        let sp = match sp {
+            StreamProcessor::Get(f) => {
+                assert!(f.is_inline());
+                f.apply(0)
+            }
+            _ => panic!("map does not start with reading"),
+        };
+        match sp {
+            StreamProcessor::Put(n, lazy_sp) => {
+                assert_eq!(n, 1);
+                assert!(lazy_sp.is_inline());
+            }
+            _ => panic!("map does not write after reading"),
+        }
+    }
+}
@@ -1,9 +1,30 @@
 //! This module defines functions which combine existing stream processors into new ones.
 //! In particular, there are nullary combinators to get writing a stream processor off the ground.
 
+use super::closures::{box_fn, box_lazy, Apply, Force};
 use super::StreamProcessor;
 
-use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+
+/// [`Either<L, R>`] defines the sum of the types `L` and `R` to tag the inputs and outputs of the choice combinators.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Either<L, R> {
+    /// Tagging an `L` as the left alternative.
+    Left(L),
+    /// Tagging an `R` as the right alternative.
+    Right(R),
+}
+
+/// [`Deferral<A, B>`] defines the outputs of stream processors which can defer inputs of type `A` besides writing outputs of type `B` (see [`defer`]).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Deferral<A, B> {
+    /// Writing a `B` as ordinary output.
+    Output(B),
+    /// Deferring an `A` until the next [`Recall`](`Deferral::Recall`).
+    Defer(A),
+    /// Re-injecting all inputs deferred so far.
+    Recall,
+}
 
 /// The function combines two stream processors into one alternating between the two whenever something is written to the ouput stream.
 /// - `sp1` is the stream processor which is in control.
@@ -11,6 +32,7 @@ use alloc::boxed::Box;
 ///
 /// This function is in analogy to running coroutines as it runs its arguments concurrently on the
 /// input stream.
+/// Hence the combined stream processor terminates as soon as the stream processor in control does.
 ///
 /// # Examples
 ///
@@ -28,23 +50,26 @@ use alloc::boxed::Box;
 ///
 /// alternate(map(id), map(negate)).eval(trues);
 /// ```
-pub fn alternate<'a, A, B: 'a>(
-    sp1: StreamProcessor<'a, A, B>,
-    sp2: StreamProcessor<'a, A, B>,
-) -> StreamProcessor<'a, A, B> {
+pub fn alternate<'a, A, B: 'a, R: 'a>(
+    sp1: StreamProcessor<'a, A, B, R>,
+    sp2: StreamProcessor<'a, A, B, R>,
+) -> StreamProcessor<'a, A, B, R> {
     match sp1 {
-        StreamProcessor::Get(f) => StreamProcessor::Get(Box::new(|a| alternate(f(a), sp2))),
+        StreamProcessor::Get(f) => StreamProcessor::Get(box_fn(|a| alternate(f.apply(a), sp2))),
         StreamProcessor::Put(b, lazy_sp) => {
-            StreamProcessor::Put(b, Box::new(|| alternate(sp2, lazy_sp())))
+            StreamProcessor::Put(b, box_lazy(|| alternate(sp2, lazy_sp.force())))
         }
+        StreamProcessor::Done(r) => StreamProcessor::Done(r),
     }
 }
 
-/// The function combines a stream processor and a family of them into one processing with the given one until an element would be written. It then uses that element to choose a stream processor from the family to carry on processing instead of writing it to the output stream.
+/// The function combines a stream processor and a family of them into one processing with the given one until it terminates. It then uses the result to choose a stream processor from the family to carry on processing.
 /// - `sp` is the stream processor.
 /// - `f` is the family of stream processors.
 ///
-/// This function is in analogy to the bind operation of monads (though we do **not** claim that it is the bind operation of an actual monad `StreamProcessor<X, _>`).
+/// This function is in analogy to the bind operation of monads.
+/// In fact, together with [`StreamProcessor::Done`] as unit it is the bind operation of the monad `StreamProcessor<A, B, _>`.
+/// In particular, it allows to run stream processors in sequence like the phases of a protocol.
 ///
 /// # Examples
 ///
@@ -55,29 +80,121 @@ pub fn alternate<'a, A, B: 'a>(
 /// use rspl::streams::infinite_lists::InfiniteList;
 /// use rspl::StreamProcessor;
 ///
-/// let is_zero = |n: isize| n == 0;
+/// let handshake = StreamProcessor::get(|n: isize| StreamProcessor::Done(n == 0));
 /// let maybe_flip_sign = |b: bool| if b { map(|n: isize| -n) } else { map(|n: isize| n) };
 ///
 /// let ones = InfiniteList::constant(1);
 ///
-/// bind(map(is_zero), maybe_flip_sign).eval(ones);
+/// bind(handshake, maybe_flip_sign).eval(ones);
 /// ```
-pub fn bind<'a, X, A: 'a, B, F>(sp: StreamProcessor<'a, X, A>, f: F) -> StreamProcessor<'a, X, B>
+pub fn bind<'a, A, B: 'a, R: 'a, S, F>(
+    sp: StreamProcessor<'a, A, B, R>,
+    f: F,
+) -> StreamProcessor<'a, A, B, S>
 where
-    F: FnOnce(A) -> StreamProcessor<'a, X, B> + 'a,
+    F: FnOnce(R) -> StreamProcessor<'a, A, B, S> + 'a,
 {
     match sp {
-        StreamProcessor::Get(g) => StreamProcessor::Get(Box::new(|a| bind(g(a), f))),
-        StreamProcessor::Put(b, _) => f(b),
+        StreamProcessor::Get(g) => StreamProcessor::Get(box_fn(|a| bind(g.apply(a), f))),
+        StreamProcessor::Put(b, lazy_sp) => {
+            StreamProcessor::Put(b, box_lazy(|| bind(lazy_sp.force(), f)))
+        }
+        StreamProcessor::Done(r) => f(r),
+    }
+}
+
+/// The function lets a stream processor process one component of the input while the other component bypasses it to be rejoined with the output.
+/// - `sp` is the stream processor processing one component.
+/// - `split` splits the input into the processed component and the bypassing component.
+/// - `join` rejoins an output of `sp` with a bypassing component.
+/// - `pending_as` are components read in advance but not yet processed by `sp`.
+/// - `cs` are bypassing components not yet rejoined with an output of `sp`.
+///
+/// The bypassing components are rejoined with the outputs of `sp` in first-in-first-out order.
+/// If `sp` writes an output while no bypassing component is buffered, the next input is read in advance and its processed component is buffered for `sp`.
+fn bypass<'a, A: 'a, B: 'a, C: 'a, D: 'a, E: 'a, R: 'a>(
+    mut sp: StreamProcessor<'a, A, B, R>,
+    split: fn(D) -> (A, C),
+    join: fn(B, C) -> E,
+    mut pending_as: VecDeque<A>,
+    mut cs: VecDeque<C>,
+) -> StreamProcessor<'a, D, E, R> {
+    loop {
+        match sp {
+            StreamProcessor::Get(f) => match pending_as.pop_front() {
+                Some(a) => {
+                    sp = f.apply(a);
+                    continue;
+                }
+                None => {
+                    return StreamProcessor::Get(box_fn(move |d| {
+                        let (a, c) = split(d);
+                        cs.push_back(c);
+                        bypass(f.apply(a), split, join, pending_as, cs)
+                    }))
+                }
+            },
+            StreamProcessor::Put(b, lazy_sp) => match cs.pop_front() {
+                Some(c) => {
+                    return StreamProcessor::Put(
+                        join(b, c),
+                        box_lazy(move || bypass(lazy_sp.force(), split, join, pending_as, cs)),
+                    )
+                }
+                None => {
+                    return StreamProcessor::Get(box_fn(move |d| {
+                        let (a, c) = split(d);
+                        pending_as.push_back(a);
+                        StreamProcessor::Put(
+                            join(b, c),
+                            box_lazy(move || bypass(lazy_sp.force(), split, join, pending_as, cs)),
+                        )
+                    }))
+                }
+            },
+            StreamProcessor::Done(r) => return StreamProcessor::Done(r),
+        }
     }
 }
 
+/// The function combines two stream processors into one processing the left alternatives of a stream of [`Either`]s with the first and the right alternatives with the second stream processor.
+/// - `sp1` is the stream processor processing the left alternatives.
+/// - `sp2` is the stream processor processing the right alternatives.
+///
+/// This function is in analogy to the `+++`-operation of arrows.
+/// It is [`left`] followed by [`right`] and hence inherits their behaviour.
+///
+/// # Examples
+///
+/// Negate the bools and increment the integers of a stream of either:
+///
+/// ```
+/// use rspl::combinators::{choice, map, Either};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let negate = |b: bool| !b;
+/// let plus_one = |n: usize| n + 1;
+///
+/// let lefts = InfiniteList::constant(Either::Left(true));
+///
+/// choice(map(negate), map(plus_one)).eval(lefts);
+/// ```
+pub fn choice<'a, A: 'a, B: 'a, C: 'a, D: 'a, R: 'a>(
+    sp1: StreamProcessor<'a, A, B, R>,
+    sp2: StreamProcessor<'a, C, D, R>,
+) -> StreamProcessor<'a, Either<A, C>, Either<B, D>, R> {
+    compose(left(sp1), right(sp2))
+}
+
 /// The function combines two stream processors into one applying the second to the result of the first.
 /// - `sp1` is the stream processor applied first.
 /// - `sp2` is the stream processor applied second.
 ///
 /// This function is in analogy to ordinary function composition.
 /// More generally, it is the composition operation in a category with stream processors as morphisms.
+/// The combined stream processor terminates as soon as the second one terminates or the first one terminates while the second one waits for input.
+/// Output of the second stream processor which is available already is written before the first one reads again, so no output is held back until the next input arrives.
 ///
 /// # Examples
 ///
@@ -94,28 +211,326 @@ where
 ///
 /// compose(map(negate), map(negate)).eval(trues);
 /// ```
-pub fn compose<'a, A, B, C: 'a>(
-    mut sp1: StreamProcessor<'a, A, B>,
-    mut sp2: StreamProcessor<'a, B, C>,
-) -> StreamProcessor<'a, A, C> {
+pub fn compose<'a, A, B, C: 'a, R: 'a>(
+    mut sp1: StreamProcessor<'a, A, B, R>,
+    mut sp2: StreamProcessor<'a, B, C, R>,
+) -> StreamProcessor<'a, A, C, R> {
+    // Writing takes precedence over reading to not hold back output of `sp2` which is available
+    // already, in line with the strategy of `eval`.
     loop {
-        match sp1 {
-            StreamProcessor::Get(f) => {
-                return StreamProcessor::Get(Box::new(|a| compose(f(a), sp2)))
+        match (sp1, sp2) {
+            (_, StreamProcessor::Done(r)) => return StreamProcessor::Done(r),
+            (sp1, StreamProcessor::Put(c, lazy_sp2)) => {
+                return StreamProcessor::Put(c, box_lazy(|| compose(sp1, lazy_sp2.force())))
             }
-            StreamProcessor::Put(b, lazy_sp1) => match sp2 {
-                StreamProcessor::Get(f) => {
-                    sp1 = lazy_sp1();
-                    sp2 = f(b);
+            (StreamProcessor::Get(f), sp2) => {
+                return StreamProcessor::Get(box_fn(|a| compose(f.apply(a), sp2)))
+            }
+            (StreamProcessor::Put(b, lazy_sp1), StreamProcessor::Get(f)) => {
+                sp1 = lazy_sp1.force();
+                sp2 = f.apply(b);
+                continue;
+            }
+            (StreamProcessor::Done(r), StreamProcessor::Get(_)) => return StreamProcessor::Done(r),
+        }
+    }
+}
+
+/// The function turns a stream processor which can defer inputs into an ordinary one.
+/// - `sp` is the stream processor which can defer inputs.
+///
+/// Whenever `sp` writes [`Deferral::Defer`], the input is queued instead of written.
+/// Whenever `sp` writes [`Deferral::Recall`], the queued inputs are re-injected in the order they were deferred, that is, they are read by `sp` before any further input of the input stream.
+/// This is meant for state machines in the style of statecharts: a state defers the inputs it cannot handle yet and every state change is accompanied by a recall such that the next state gets another chance to handle them.
+/// Re-injected inputs can be deferred again.
+///
+/// # Examples
+///
+/// Printing characters only when unlocked without losing the ones arriving before:
+///
+/// ```
+/// use rspl::combinators::{defer, Deferral};
+/// use rspl::StreamProcessor;
+///
+/// #[derive(Copy, Clone)]
+/// enum Event {
+///     Unlock,
+///     Print(char),
+/// }
+///
+/// fn locked<'a>() -> StreamProcessor<'a, Event, Deferral<Event, char>> {
+///     StreamProcessor::get(|event| match event {
+///         Event::Unlock => StreamProcessor::put(Deferral::Recall, unlocked),
+///         Event::Print(_) => StreamProcessor::put(Deferral::Defer(event), locked),
+///     })
+/// }
+///
+/// fn unlocked<'a>() -> StreamProcessor<'a, Event, Deferral<Event, char>> {
+///     StreamProcessor::get(|event| match event {
+///         Event::Unlock => unlocked(),
+///         Event::Print(c) => StreamProcessor::put(Deferral::Output(c), unlocked),
+///     })
+/// }
+///
+/// let events = [Event::Print('a'), Event::Print('b'), Event::Unlock, Event::Print('c')];
+///
+/// let result = defer(locked()).eval_iter(events).collect::<String>();
+///
+/// assert_eq!(result, "abc");
+/// ```
+pub fn defer<'a, A: 'a, B: 'a, R: 'a>(
+    sp: StreamProcessor<'a, A, Deferral<A, B>, R>,
+) -> StreamProcessor<'a, A, B, R> {
+    defer_with(sp, VecDeque::new(), VecDeque::new())
+}
+
+/// The function is the same as [`defer`] but with some inputs already deferred and some inputs already pending to be re-injected.
+/// - `sp` is the stream processor which can defer inputs.
+/// - `deferred` are the inputs deferred.
+/// - `recalled` are the inputs pending to be re-injected.
+fn defer_with<'a, A: 'a, B: 'a, R: 'a>(
+    mut sp: StreamProcessor<'a, A, Deferral<A, B>, R>,
+    mut deferred: VecDeque<A>,
+    mut recalled: VecDeque<A>,
+) -> StreamProcessor<'a, A, B, R> {
+    loop {
+        match sp {
+            StreamProcessor::Get(f) => match recalled.pop_front() {
+                Some(a) => {
+                    sp = f.apply(a);
                     continue;
                 }
-                StreamProcessor::Put(c, lazy_sp2) => {
-                    return StreamProcessor::Put(
-                        c,
-                        Box::new(|| compose(StreamProcessor::Put(b, lazy_sp1), lazy_sp2())),
-                    )
+                None => {
+                    return StreamProcessor::Get(box_fn(move |a| {
+                        defer_with(f.apply(a), deferred, recalled)
+                    }))
+                }
+            },
+            StreamProcessor::Put(Deferral::Output(b), lazy_sp) => {
+                return StreamProcessor::Put(
+                    b,
+                    box_lazy(move || defer_with(lazy_sp.force(), deferred, recalled)),
+                )
+            }
+            StreamProcessor::Put(Deferral::Defer(a), lazy_sp) => {
+                deferred.push_back(a);
+                sp = lazy_sp.force();
+                continue;
+            }
+            StreamProcessor::Put(Deferral::Recall, lazy_sp) => {
+                // The deferred inputs are older than the ones still pending from a former recall.
+                deferred.append(&mut recalled);
+                recalled = deferred;
+                deferred = VecDeque::new();
+                sp = lazy_sp.force();
+                continue;
+            }
+            StreamProcessor::Done(r) => return StreamProcessor::Done(r),
+        }
+    }
+}
+
+/// Construct the stream processor which drops a certain number of inputs and passes the rest through.
+/// - `n` is the number of inputs to be dropped.
+///
+/// The function is in analogy to the drop-function on lists which is well-known in functional programming (and to [`Iterator::skip`]).
+///
+/// # Examples
+///
+/// Drop the first two elements of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::drop;
+/// use rspl::StreamProcessor;
+///
+/// let sp: StreamProcessor<_, _> = drop(2);
+///
+/// let result = sp.eval_iter(0..5).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![2, 3, 4]);
+/// ```
+pub fn drop<'a, A: 'a, R: 'a>(n: usize) -> StreamProcessor<'a, A, A, R> {
+    if n == 0 {
+        map(|a| a)
+    } else {
+        StreamProcessor::Get(box_fn(move |_| drop(n - 1)))
+    }
+}
+
+/// Construct the stream processor which drops the inputs as long as they satisfy a given predicate and passes the rest through.
+/// - `p` is the predicate.
+///
+/// The function is in analogy to the dropWhile-function on lists which is well-known in functional programming.
+/// In particular, the first input not satisfying `p` is passed through.
+///
+/// # Examples
+///
+/// Drop the leading `0`s of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::drop_while;
+/// use rspl::StreamProcessor;
+///
+/// let is_zero = |n: &usize| *n == 0;
+///
+/// let sp: StreamProcessor<_, _> = drop_while(is_zero);
+///
+/// let result = sp.eval_iter([0, 0, 1, 0]).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![1, 0]);
+/// ```
+pub fn drop_while<'a, A: 'a, R: 'a, P>(p: P) -> StreamProcessor<'a, A, A, R>
+where
+    P: Fn(&A) -> bool + 'a,
+{
+    StreamProcessor::Get(box_fn(|a: A| {
+        if p(&a) {
+            drop_while(p)
+        } else {
+            StreamProcessor::Put(a, box_lazy(|| map(|a| a)))
+        }
+    }))
+}
+
+/// Construct the stream processor which duplicates every input.
+fn duplicate<'a, A: Clone + 'a, R: 'a>() -> StreamProcessor<'a, A, (A, A), R> {
+    StreamProcessor::Get(box_fn(|a: A| {
+        StreamProcessor::Put((a.clone(), a), box_lazy(duplicate))
+    }))
+}
+
+/// The function combines two stream processors with the same output type into one processing the left alternatives of a stream of [`Either`]s with the first and the right alternatives with the second stream processor.
+/// - `sp1` is the stream processor processing the left alternatives.
+/// - `sp2` is the stream processor processing the right alternatives.
+///
+/// This function is in analogy to the `|||`-operation of arrows.
+/// It is [`choice`] followed by forgetting the tags of the outputs.
+///
+/// # Examples
+///
+/// Turn a stream of either bools or integers into a stream of bools:
+///
+/// ```
+/// use rspl::combinators::{fanin, map, Either};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let id = |b: bool| b;
+/// let is_zero = |n: usize| n == 0;
+///
+/// let rights = InfiniteList::constant(Either::Right(0));
+///
+/// fanin(map(id), map(is_zero)).eval(rights);
+/// ```
+pub fn fanin<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp1: StreamProcessor<'a, A, C, R>,
+    sp2: StreamProcessor<'a, B, C, R>,
+) -> StreamProcessor<'a, Either<A, B>, C, R> {
+    compose(choice(sp1, sp2), untag())
+}
+
+/// The function combines two stream processors into one processing every input with both and pairing their outputs.
+/// - `sp1` is the stream processor producing the first components of the output.
+/// - `sp2` is the stream processor producing the second components of the output.
+///
+/// This function is in analogy to the `&&&`-operation of arrows.
+/// It is [`split`] preceded by duplicating the input and hence inherits its buffering behaviour.
+///
+/// # Examples
+///
+/// Pair a stream of bools with its negation:
+///
+/// ```
+/// use rspl::combinators::{fanout, map};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let id = |b: bool| b;
+/// let negate = |b: bool| !b;
+///
+/// let trues = InfiniteList::constant(true);
+///
+/// fanout(map(id), map(negate)).eval(trues);
+/// ```
+pub fn fanout<'a, A: Clone + 'a, B: 'a, C: 'a, R: 'a>(
+    sp1: StreamProcessor<'a, A, B, R>,
+    sp2: StreamProcessor<'a, A, C, R>,
+) -> StreamProcessor<'a, A, (B, C), R> {
+    compose(duplicate(), split(sp1, sp2))
+}
+
+/// The function turns a stream processor into one feeding back the right alternatives of its output to its own input.
+/// - `sp` is the stream processor whose left alternatives are the actual in- and output and whose right alternatives are fed back.
+///
+/// This function is in analogy to the `loop`-operation of arrows.
+/// The fed back values are delayed: a value written as right alternative is buffered in a queue and only read by `sp` when it reads the next time.
+/// Then the buffered values take precedence over the actual input, that is, the actual input is only read when no fed back value is pending.
+/// So the combined stream processor is productive as long as `sp` does not write right alternatives forever without reading or writing left alternatives.
+///
+/// # Examples
+///
+/// Compute the running sums of a stream of integers by feeding back the partial sum:
+///
+/// ```
+/// use rspl::combinators::{feedback, Either};
+/// use rspl::StreamProcessor;
+///
+/// type Sum<'a> = StreamProcessor<'a, Either<usize, usize>, Either<usize, usize>>;
+///
+/// fn step<'a>() -> Sum<'a> {
+///     StreamProcessor::get(|e| match e {
+///         Either::Right(sum) => StreamProcessor::get(move |e| match e {
+///             Either::Left(n) => StreamProcessor::put(Either::Left(sum + n), move || {
+///                 StreamProcessor::put(Either::Right(sum + n), step)
+///             }),
+///             Either::Right(_) => step(),
+///         }),
+///         Either::Left(_) => step(),
+///     })
+/// }
+///
+/// let sums = feedback(StreamProcessor::put(Either::Right(0), step)).eval_iter([1, 2, 3]);
+///
+/// assert_eq!(sums.collect::<Vec<_>>(), vec![1, 3, 6]);
+/// ```
+pub fn feedback<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp: StreamProcessor<'a, Either<A, C>, Either<B, C>, R>,
+) -> StreamProcessor<'a, A, B, R> {
+    feedback_with(sp, VecDeque::new())
+}
+
+/// The function is the same as [`feedback`] but with some values already pending to be fed back.
+/// - `sp` is the stream processor whose right alternatives are fed back.
+/// - `cs` are the values pending to be fed back.
+fn feedback_with<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    mut sp: StreamProcessor<'a, Either<A, C>, Either<B, C>, R>,
+    mut cs: VecDeque<C>,
+) -> StreamProcessor<'a, A, B, R> {
+    loop {
+        match sp {
+            StreamProcessor::Get(f) => match cs.pop_front() {
+                Some(c) => {
+                    sp = f.apply(Either::Right(c));
+                    continue;
+                }
+                None => {
+                    return StreamProcessor::Get(box_fn(move |a| {
+                        feedback_with(f.apply(Either::Left(a)), cs)
+                    }))
                 }
             },
+            StreamProcessor::Put(Either::Left(b), lazy_sp) => {
+                return StreamProcessor::Put(
+                    b,
+                    box_lazy(move || feedback_with(lazy_sp.force(), cs)),
+                )
+            }
+            StreamProcessor::Put(Either::Right(c), lazy_sp) => {
+                cs.push_back(c);
+                sp = lazy_sp.force();
+                continue;
+            }
+            StreamProcessor::Done(r) => return StreamProcessor::Done(r),
         }
     }
 }
@@ -140,19 +555,102 @@ pub fn compose<'a, A, B, C: 'a>(
 ///
 /// filter(is_greater_zero).eval(InfiniteList::cons(0, ones));
 /// ```
-pub fn filter<'a, A, P>(p: P) -> StreamProcessor<'a, A, A>
+pub fn filter<'a, A, R: 'a, P>(p: P) -> StreamProcessor<'a, A, A, R>
 where
     P: Fn(&A) -> bool + 'a,
 {
-    StreamProcessor::Get(Box::new(|a: A| {
+    StreamProcessor::Get(box_fn(|a: A| {
         if p(&a) {
-            StreamProcessor::Put(a, Box::new(|| filter(p)))
+            StreamProcessor::Put(a, box_lazy(|| filter(p)))
         } else {
             filter(p)
         }
     }))
 }
 
+/// Construct the stream processor which processes the first components of a stream of pairs with a given stream processor leaving the second components untouched.
+/// - `sp` is the stream processor processing the first components.
+///
+/// This function is in analogy to the `first`-operation of arrows.
+/// The second components are buffered in a queue until they are paired with the outputs of `sp` in first-in-first-out order.
+/// So, if `sp` writes exactly one output per input, every output is paired with the second component of the input it stems from.
+/// If `sp` writes an output while no second component is buffered, the next input is read in advance: its second component is paired with the output and its first component is buffered until `sp` reads.
+///
+/// # Examples
+///
+/// Negate the first components of a stream of pairs of bools:
+///
+/// ```
+/// use rspl::combinators::{first, map};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let negate = |b: bool| !b;
+///
+/// let pairs = InfiniteList::constant((true, true));
+///
+/// first(map(negate)).eval(pairs);
+/// ```
+pub fn first<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp: StreamProcessor<'a, A, B, R>,
+) -> StreamProcessor<'a, (A, C), (B, C), R> {
+    bypass(
+        sp,
+        |(a, c)| (a, c),
+        |b, c| (b, c),
+        VecDeque::new(),
+        VecDeque::new(),
+    )
+}
+
+/// Construct the stream processor which folds every chunk of a certain number of inputs into one output.
+/// - `n` is the number of inputs per chunk.
+/// - `init` is the initial state of the fold of every chunk.
+/// - `f` is the function updating the state of the fold with an input.
+///
+/// The function is in analogy to folding lists in chunks.
+/// The outputs are written after every `n`th input, so incomplete chunks are never written.
+///
+/// # Panics
+///
+/// A panic is caused if `n` is `0`.
+///
+/// # Examples
+///
+/// Sum up every three integers of a stream:
+///
+/// ```
+/// use rspl::combinators::fold_every;
+/// use rspl::StreamProcessor;
+///
+/// let sum = |acc: usize, n: usize| acc + n;
+///
+/// let sp: StreamProcessor<_, _> = fold_every(3, 0, sum);
+///
+/// let result = sp.eval_iter(1..=7).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![6, 15]);
+/// ```
+pub fn fold_every<'a, A: 'a, R: 'a, S: Clone + 'a, F>(
+    n: usize,
+    init: S,
+    f: F,
+) -> StreamProcessor<'a, A, S, R>
+where
+    F: Fn(S, A) -> S + 'a,
+{
+    assert!(n > 0, "chunks must not be empty");
+
+    mealy((0, init.clone()), move |(k, state), a| {
+        let state = f(state, a);
+        if k + 1 == n {
+            ((0, init.clone()), Some(state))
+        } else {
+            ((k + 1, state), None)
+        }
+    })
+}
+
 /// Construct the stream processor which produces an output without reading from the input according to a function remembering its state.
 /// - `body` is the function producing the output in state-passing style.
 /// - `state` is the initial state.
@@ -174,12 +672,50 @@ where
 ///
 /// generate(int_mod_3, 0).eval(units);
 /// ```
-pub fn generate<'a, A, B, S: 'a, F>(body: F, state: S) -> StreamProcessor<'a, A, B>
+pub fn generate<'a, A, B, R: 'a, S: 'a, F>(body: F, state: S) -> StreamProcessor<'a, A, B, R>
 where
     F: Fn(S) -> (B, S) + 'a,
 {
     let (x, state) = body(state);
-    StreamProcessor::Put(x, Box::new(|| generate(body, state)))
+    StreamProcessor::Put(x, box_lazy(|| generate(body, state)))
+}
+
+/// Construct the stream processor which processes the left alternatives of a stream of [`Either`]s with a given stream processor while passing the right alternatives through.
+/// - `sp` is the stream processor processing the left alternatives.
+///
+/// This function is in analogy to the `left`-operation of arrows.
+/// Nothing is buffered: the outputs of `sp` are tagged as left and written as soon as they are available, and every right alternative is written as soon as it is read.
+///
+/// # Examples
+///
+/// Negate the left alternatives of a stream of either bools or integers:
+///
+/// ```
+/// use rspl::combinators::{left, map, Either};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let negate = |b: bool| !b;
+///
+/// let rights = InfiniteList::constant(Either::<bool, usize>::Right(0));
+///
+/// left(map(negate)).eval(rights);
+/// ```
+pub fn left<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp: StreamProcessor<'a, A, B, R>,
+) -> StreamProcessor<'a, Either<A, C>, Either<B, C>, R> {
+    match sp {
+        StreamProcessor::Get(f) => StreamProcessor::Get(box_fn(|e| match e {
+            Either::Left(a) => left(f.apply(a)),
+            Either::Right(c) => {
+                StreamProcessor::Put(Either::Right(c), box_lazy(|| left(StreamProcessor::Get(f))))
+            }
+        })),
+        StreamProcessor::Put(b, lazy_sp) => {
+            StreamProcessor::Put(Either::Left(b), box_lazy(|| left(lazy_sp.force())))
+        }
+        StreamProcessor::Done(r) => StreamProcessor::Done(r),
+    }
 }
 
 /// Construct the stream processor which applies a given closure to each element of the input stream.
@@ -202,28 +738,351 @@ where
 ///
 /// map(negate).eval(trues);
 /// ```
-pub fn map<'a, A, B, F>(f: F) -> StreamProcessor<'a, A, B>
+pub fn map<'a, A, B, R: 'a, F>(f: F) -> StreamProcessor<'a, A, B, R>
 where
     F: Fn(A) -> B + 'a,
 {
-    StreamProcessor::Get(Box::new(|a: A| {
-        StreamProcessor::Put(f(a), Box::new(|| map(f)))
+    StreamProcessor::Get(box_fn(|a: A| {
+        StreamProcessor::Put(f(a), box_lazy(|| map(f)))
     }))
 }
 
-#[cfg(feature = "std")]
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::streams::overeager_receivers::OvereagerReceiver;
-    use crate::streams::Stream;
+/// Construct the stream processor which behaves like a Mealy machine given by a state and a transition function.
+/// - `state` is the initial state of the machine.
+/// - `transition` is the function mapping the current state and an input to the next state and possibly an output.
+///
+/// The function allows to define stateful stream processors without writing a recursive function for every state.
+/// It is in analogy to the mapAccumL-function on lists which is well-known in functional programming but can also leave out outputs.
+///
+/// # Examples
+///
+/// Report the changes of a stream of bools:
+///
+/// ```
+/// use rspl::combinators::mealy;
+/// use rspl::StreamProcessor;
+///
+/// let changes = |last: bool, b: bool| (b, (b != last).then_some(b));
+///
+/// let sp: StreamProcessor<_, _> = mealy(false, changes);
+///
+/// let result = sp.eval_iter([false, true, true, false]).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![true, false]);
+/// ```
+pub fn mealy<'a, A: 'a, B: 'a, R: 'a, S: 'a, F>(
+    state: S,
+    transition: F,
+) -> StreamProcessor<'a, A, B, R>
+where
+    F: Fn(S, A) -> (S, Option<B>) + 'a,
+{
+    StreamProcessor::Get(box_fn(|a: A| match transition(state, a) {
+        (state, Some(b)) => StreamProcessor::Put(b, box_lazy(|| mealy(state, transition))),
+        (state, None) => mealy(state, transition),
+    }))
+}
 
-    use crate::assert_head_eq;
-    use crate::assert_tail_starts_with;
-    use crate::enqueue;
+/// Construct the stream processor which behaves like a Moore machine given by a state, a transition function and an output function.
+/// - `state` is the initial state of the machine.
+/// - `transition` is the function mapping the current state and an input to the next state.
+/// - `output` is the function determining the output written on entering a state.
+///
+/// Other than with [`mealy`] the outputs only depend on the states.
+/// Every state, including the initial one, writes its output on entry, so there is exactly one output more than inputs read.
+///
+/// # Examples
+///
+/// Count the inputs modulo three:
+///
+/// ```
+/// use rspl::combinators::moore;
+/// use rspl::runners::PushRunner;
+/// use rspl::StreamProcessor;
+///
+/// let sp: StreamProcessor<_, _> = moore(0, |n: usize, ()| (n + 1) % 3, |n: &usize| *n);
+///
+/// let mut runner = PushRunner::new(sp);
+///
+/// assert_eq!(runner.pending().collect::<Vec<_>>(), vec![0]);
+/// assert_eq!(runner.feed(()).collect::<Vec<_>>(), vec![1]);
+/// ```
+pub fn moore<'a, A: 'a, B: 'a, R: 'a, S: 'a, F, G>(
+    state: S,
+    transition: F,
+    output: G,
+) -> StreamProcessor<'a, A, B, R>
+where
+    F: Fn(S, A) -> S + 'a,
+    G: Fn(&S) -> B + 'a,
+{
+    StreamProcessor::Put(
+        output(&state),
+        box_lazy(|| {
+            StreamProcessor::Get(box_fn(|a: A| {
+                moore(transition(state, a), transition, output)
+            }))
+        }),
+    )
+}
 
-    #[test]
-    fn test_alternate() {
+/// Construct the stream processor which processes the right alternatives of a stream of [`Either`]s with a given stream processor while passing the left alternatives through.
+/// - `sp` is the stream processor processing the right alternatives.
+///
+/// This function is in analogy to the `right`-operation of arrows.
+/// It behaves as [`left`] with the roles of the alternatives swapped.
+///
+/// # Examples
+///
+/// Negate the right alternatives of a stream of either integers or bools:
+///
+/// ```
+/// use rspl::combinators::{map, right, Either};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let negate = |b: bool| !b;
+///
+/// let rights = InfiniteList::constant(Either::<usize, bool>::Right(true));
+///
+/// right(map(negate)).eval(rights);
+/// ```
+pub fn right<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp: StreamProcessor<'a, A, B, R>,
+) -> StreamProcessor<'a, Either<C, A>, Either<C, B>, R> {
+    match sp {
+        StreamProcessor::Get(f) => StreamProcessor::Get(box_fn(|e| match e {
+            Either::Left(c) => {
+                StreamProcessor::Put(Either::Left(c), box_lazy(|| right(StreamProcessor::Get(f))))
+            }
+            Either::Right(a) => right(f.apply(a)),
+        })),
+        StreamProcessor::Put(b, lazy_sp) => {
+            StreamProcessor::Put(Either::Right(b), box_lazy(|| right(lazy_sp.force())))
+        }
+        StreamProcessor::Done(r) => StreamProcessor::Done(r),
+    }
+}
+
+/// Construct the stream processor which accumulates the inputs in a state and writes every intermediate state.
+/// - `init` is the initial state.
+/// - `f` is the function updating the state with an input.
+///
+/// The function is in analogy to the scanl-function on lists which is well-known in functional programming.
+/// However, the initial state is not written, so there is exactly one output per input.
+///
+/// # Examples
+///
+/// Compute the running sums of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::scan;
+/// use rspl::StreamProcessor;
+///
+/// let sum = |acc: usize, n: usize| acc + n;
+///
+/// let sp: StreamProcessor<_, _> = scan(0, sum);
+///
+/// let result = sp.eval_iter(1..=4).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![1, 3, 6, 10]);
+/// ```
+pub fn scan<'a, A: 'a, R: 'a, S: Clone + 'a, F>(init: S, f: F) -> StreamProcessor<'a, A, S, R>
+where
+    F: Fn(S, A) -> S + 'a,
+{
+    mealy(init, move |state, a| {
+        let state = f(state, a);
+        (state.clone(), Some(state))
+    })
+}
+
+/// Construct the stream processor which processes the second components of a stream of pairs with a given stream processor leaving the first components untouched.
+/// - `sp` is the stream processor processing the second components.
+///
+/// This function is in analogy to the `second`-operation of arrows.
+/// It buffers the first components as [`first`] buffers the second ones.
+///
+/// # Examples
+///
+/// Negate the second components of a stream of pairs of bools:
+///
+/// ```
+/// use rspl::combinators::{map, second};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let negate = |b: bool| !b;
+///
+/// let pairs = InfiniteList::constant((true, true));
+///
+/// second(map(negate)).eval(pairs);
+/// ```
+pub fn second<'a, A: 'a, B: 'a, C: 'a, R: 'a>(
+    sp: StreamProcessor<'a, A, B, R>,
+) -> StreamProcessor<'a, (C, A), (C, B), R> {
+    bypass(
+        sp,
+        |(c, a)| (a, c),
+        |b, c| (c, b),
+        VecDeque::new(),
+        VecDeque::new(),
+    )
+}
+
+/// Construct the stream processor which skips the inputs until one satisfies a given predicate and passes the rest through.
+/// - `p` is the predicate.
+///
+/// Other than with [`drop_while`] the input satisfying `p` is skipped as well.
+/// So, the function is handy to skip everything up to some marker.
+///
+/// # Examples
+///
+/// Skip everything up to the first `0` of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::skip_until;
+/// use rspl::StreamProcessor;
+///
+/// let is_zero = |n: &usize| *n == 0;
+///
+/// let sp: StreamProcessor<_, _> = skip_until(is_zero);
+///
+/// let result = sp.eval_iter([1, 0, 2, 0]).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![2, 0]);
+/// ```
+pub fn skip_until<'a, A: 'a, R: 'a, P>(p: P) -> StreamProcessor<'a, A, A, R>
+where
+    P: Fn(&A) -> bool + 'a,
+{
+    StreamProcessor::Get(box_fn(
+        |a: A| {
+            if p(&a) {
+                map(|a| a)
+            } else {
+                skip_until(p)
+            }
+        },
+    ))
+}
+
+/// The function combines two stream processors into one processing the first components of a stream of pairs with the first and the second components with the second stream processor.
+/// - `sp1` is the stream processor processing the first components.
+/// - `sp2` is the stream processor processing the second components.
+///
+/// This function is in analogy to the `***`-operation of arrows.
+/// It is [`first`] followed by [`second`] and hence inherits their buffering behaviour.
+/// In particular, if both stream processors write exactly one output per input, the outputs are paired as the inputs.
+///
+/// # Examples
+///
+/// Negate the second components of a stream of pairs of bools while keeping the first:
+///
+/// ```
+/// use rspl::combinators::{map, split};
+/// use rspl::streams::infinite_lists::InfiniteList;
+/// use rspl::StreamProcessor;
+///
+/// let id = |b: bool| b;
+/// let negate = |b: bool| !b;
+///
+/// let pairs = InfiniteList::constant((true, true));
+///
+/// split(map(id), map(negate)).eval(pairs);
+/// ```
+pub fn split<'a, A: 'a, B: 'a, C: 'a, D: 'a, R: 'a>(
+    sp1: StreamProcessor<'a, A, B, R>,
+    sp2: StreamProcessor<'a, C, D, R>,
+) -> StreamProcessor<'a, (A, C), (B, D), R> {
+    compose(first(sp1), second(sp2))
+}
+
+/// Construct the stream processor which passes a certain number of inputs through and terminates afterwards.
+/// - `n` is the number of inputs to be passed through.
+///
+/// The function is in analogy to the take-function on lists which is well-known in functional programming.
+/// As the stream processor terminates, there is no more output after the `n`th one.
+/// So, it is best evaluated with [`eval_finite`](`StreamProcessor::eval_finite`) or [`eval_iter`](`StreamProcessor::eval_iter`) or continued with [`bind`].
+///
+/// # Examples
+///
+/// Take the first two elements of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::take;
+///
+/// let result = take(2).eval_iter(0..).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![0, 1]);
+/// ```
+pub fn take<'a, A: 'a>(n: usize) -> StreamProcessor<'a, A, A, ()> {
+    if n == 0 {
+        StreamProcessor::Done(())
+    } else {
+        StreamProcessor::Get(box_fn(move |a| {
+            StreamProcessor::Put(a, box_lazy(move || take(n - 1)))
+        }))
+    }
+}
+
+/// Construct the stream processor which passes the inputs through as long as they satisfy a given predicate and terminates afterwards.
+/// - `p` is the predicate.
+///
+/// The function is in analogy to the takeWhile-function on lists which is well-known in functional programming.
+/// As the stream processor terminates, there is no more output after the last input satisfying `p`.
+/// The result of the stream processor is the first input not satisfying `p` such that it is not lost when continuing with [`bind`].
+///
+/// # Examples
+///
+/// Take the leading `0`s of a stream of integers:
+///
+/// ```
+/// use rspl::combinators::take_while;
+///
+/// let is_zero = |n: &usize| *n == 0;
+///
+/// let result = take_while(is_zero).eval_iter([0, 0, 1, 0]).collect::<Vec<_>>();
+///
+/// assert_eq!(result, vec![0, 0]);
+/// ```
+pub fn take_while<'a, A: 'a, P>(p: P) -> StreamProcessor<'a, A, A, A>
+where
+    P: Fn(&A) -> bool + 'a,
+{
+    StreamProcessor::Get(box_fn(|a: A| {
+        if p(&a) {
+            StreamProcessor::Put(a, box_lazy(|| take_while(p)))
+        } else {
+            StreamProcessor::Done(a)
+        }
+    }))
+}
+
+/// Construct the stream processor which forgets the tags of a stream of [`Either`]s.
+fn untag<'a, A: 'a, R: 'a>() -> StreamProcessor<'a, Either<A, A>, A, R> {
+    StreamProcessor::Get(box_fn(|e| match e {
+        Either::Left(a) | Either::Right(a) => StreamProcessor::Put(a, box_lazy(untag)),
+    }))
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runners::PushRunner;
+    use crate::streams::overeager_receivers::OvereagerReceiver;
+    use crate::streams::Stream;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+    use crate::enqueue;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_alternate() {
         let is_greater_zero = |n: &i8| *n > 0;
         let is_less_zero = |n: &i8| *n < 0;
 
@@ -237,19 +1096,38 @@ mod tests {
         assert_tail_starts_with!(result, [-1, 1]);
     }
 
+    #[test]
+    fn test_alternate_done() {
+        let sp: StreamProcessor<(), usize, bool> = alternate(
+            StreamProcessor::put(1, || StreamProcessor::Done(true)),
+            StreamProcessor::Done(false),
+        );
+
+        if let StreamProcessor::Put(n, lazy_sp) = sp {
+            assert_eq!(n, 1);
+            assert!(matches!(lazy_sp.force(), StreamProcessor::Done(false)));
+        } else {
+            panic!();
+        }
+    }
+
     #[test]
     fn test_bind() {
-        let is_zero = |n: usize| n == 0;
+        let is_zero =
+            |n: usize| StreamProcessor::get(move |m: usize| StreamProcessor::Done(m == n));
 
-        let sp = bind(map(is_zero), |b: bool| {
+        let sp = bind(is_zero(0), move |b: bool| {
             if b {
-                bind(map(is_zero), |b: bool| {
-                    if b {
-                        map(|n| n + 2)
-                    } else {
-                        map(|n| n + 1)
-                    }
-                })
+                bind(
+                    is_zero(0),
+                    |b: bool| {
+                        if b {
+                            map(|n| n + 2)
+                        } else {
+                            map(|n| n + 1)
+                        }
+                    },
+                )
             } else {
                 filter(|n| *n > 0)
             }
@@ -263,6 +1141,36 @@ mod tests {
         assert_tail_starts_with!(result, [2, 3]);
     }
 
+    #[test]
+    fn test_bind_sequence() {
+        let handshake = StreamProcessor::put(0, || StreamProcessor::Done(()));
+        let teardown = StreamProcessor::put(2, || StreamProcessor::Done(()));
+
+        let sp = bind(handshake, |()| {
+            bind(StreamProcessor::put(1, || teardown), |()| map(|n: usize| n))
+        });
+
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+        enqueue!(tx, [3, 4]);
+
+        let mut result = sp.eval(stream);
+        assert_head_eq!(result, 0);
+        assert_tail_starts_with!(result, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_choice() {
+        let sp: StreamProcessor<_, _> = choice(map(|n: usize| n + 1), map(|b: bool| !b));
+
+        let result = sp
+            .eval_iter([Either::Left(0), Either::Right(true), Either::Left(1)])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![Either::Left(1), Either::Right(false), Either::Left(2)]
+        );
+    }
+
     #[test]
     fn test_compose() {
         let plus_one = |n: usize| n + 1;
@@ -277,6 +1185,156 @@ mod tests {
         assert_tail_starts_with!(result, [3, 4]);
     }
 
+    #[test]
+    fn test_compose_done() {
+        let sp: StreamProcessor<(), usize, bool> = compose(
+            StreamProcessor::put(1, || StreamProcessor::Done(true)),
+            StreamProcessor::get(|n: usize| {
+                StreamProcessor::put(n + 1, || {
+                    StreamProcessor::get(|_| StreamProcessor::Done(false))
+                })
+            }),
+        );
+
+        if let StreamProcessor::Put(n, lazy_sp) = sp {
+            assert_eq!(n, 2);
+            assert!(matches!(lazy_sp.force(), StreamProcessor::Done(true)));
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn test_compose_order() {
+        let sp: StreamProcessor<_, _> = compose(
+            map(|n: usize| n),
+            StreamProcessor::put(0, || map(|n: usize| n)),
+        );
+        assert!(matches!(sp, StreamProcessor::Put(0, _)));
+
+        let sp: StreamProcessor<_, _> = compose(map(|n: usize| n + 1), map(|n: usize| 2 * n));
+        assert_eq!(sp.eval_iter([1, 2]).collect::<Vec<_>>(), vec![4, 6]);
+    }
+
+    #[test]
+    #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+    fn test_defer() {
+        // This stream processor writes the even inputs right away but defers the odd ones until
+        // the next even input.
+        fn evens<'a>() -> StreamProcessor<'a, usize, Deferral<usize, usize>> {
+            StreamProcessor::get(|n: usize| {
+                if n % 2 == 0 {
+                    StreamProcessor::put(Deferral::Output(n), || {
+                        StreamProcessor::put(Deferral::Recall, odds)
+                    })
+                } else {
+                    StreamProcessor::put(Deferral::Defer(n), evens)
+                }
+            })
+        }
+
+        // This stream processor writes the first odd input and defers everything else until then.
+        fn odds<'a>() -> StreamProcessor<'a, usize, Deferral<usize, usize>> {
+            StreamProcessor::get(|n: usize| {
+                if n % 2 == 0 {
+                    StreamProcessor::put(Deferral::Defer(n), odds)
+                } else {
+                    StreamProcessor::put(Deferral::Output(n), || {
+                        StreamProcessor::put(Deferral::Recall, evens)
+                    })
+                }
+            })
+        }
+
+        let result = defer(evens())
+            .eval_iter([1, 3, 5, 2, 4, 6, 7])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![2, 1, 4, 3, 6, 5]);
+    }
+
+    #[test]
+    fn test_defer_done() {
+        let sp = StreamProcessor::put(Deferral::<usize, usize>::Recall, || {
+            StreamProcessor::Done(true)
+        });
+
+        assert!(matches!(defer(sp), StreamProcessor::Done(true)));
+    }
+
+    #[test]
+    fn test_drop() {
+        let eval = |n, ns: &[usize]| {
+            let sp: StreamProcessor<_, _> = drop(n);
+            sp.eval_iter(ns.to_vec()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(eval(2, &[1, 2, 3, 4]), vec![3, 4]);
+        assert_eq!(eval(0, &[1, 2]), vec![1, 2]);
+        assert_eq!(eval(3, &[1, 2]), vec![]);
+    }
+
+    #[test]
+    fn test_drop_while() {
+        let is_less_two = |n: &usize| *n < 2;
+
+        let sp: StreamProcessor<_, _> = drop_while(is_less_two);
+        let result = sp.eval_iter([0, 1, 2, 1, 3]).collect::<Vec<_>>();
+        assert_eq!(result, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_fanin() {
+        let sp: StreamProcessor<_, _> = fanin(map(|n: usize| n + 1), map(|b: bool| usize::from(b)));
+
+        let result = sp
+            .eval_iter([Either::Left(1), Either::Right(true), Either::Right(false)])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_fanout() {
+        let sp: StreamProcessor<_, _> = fanout(map(|n: usize| n + 1), map(|n: usize| 2 * n));
+
+        let result = sp.eval_iter([1, 2, 3]).collect::<Vec<_>>();
+        assert_eq!(result, vec![(2, 2), (3, 4), (4, 6)]);
+    }
+
+    #[test]
+    fn test_feedback() {
+        // This stream processor echoes every input twice by feeding it back once.
+        fn echo<'a>() -> StreamProcessor<'a, Either<usize, usize>, Either<usize, usize>> {
+            StreamProcessor::get(|e| match e {
+                Either::Left(n) => StreamProcessor::put(Either::Left(n), move || {
+                    StreamProcessor::put(Either::Right(n), echo)
+                }),
+                Either::Right(n) => StreamProcessor::put(Either::Left(n), echo),
+            })
+        }
+
+        let result = feedback(echo()).eval_iter([1, 2]).collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_feedback_delay() {
+        let sp = StreamProcessor::get(|e| match e {
+            Either::Left(n) | Either::Right(n) => {
+                StreamProcessor::put(Either::Right(n + 10), move || {
+                    StreamProcessor::put(Either::Left(n), || {
+                        map(|e| match e {
+                            Either::Left(n) | Either::Right(n) => Either::Left(n),
+                        })
+                    })
+                })
+            }
+        });
+
+        let sp: StreamProcessor<_, _> = feedback(sp);
+        let result = sp.eval_iter([3, 4]).collect::<Vec<_>>();
+        assert_eq!(result, vec![3, 13, 4]);
+    }
+
     #[test]
     fn test_filter() {
         let is_greater_zero = |n: &usize| *n > 0;
@@ -291,6 +1349,104 @@ mod tests {
         assert_tail_starts_with!(result, [2]);
     }
 
+    #[test]
+    fn test_first() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp: StreamProcessor<_, _> = first(filter(is_greater_zero));
+
+        let result = sp
+            .eval_iter([(0, 'a'), (1, 'b'), (2, 'c')])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![(1, 'a'), (2, 'b')]);
+    }
+
+    #[test]
+    fn test_first_ahead() {
+        let sp: StreamProcessor<_, _> = first(StreamProcessor::put(0, || map(|n: usize| n)));
+
+        let result = sp.eval_iter([(1, 'a'), (2, 'b')]).collect::<Vec<_>>();
+        assert_eq!(result, vec![(0, 'a'), (1, 'b')]);
+    }
+
+    #[test]
+    fn test_first_laws() {
+        const INPUT: [(usize, usize); 5] = [(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)];
+
+        #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+        let is_even = |n: &usize| *n % 2 == 0;
+        let plus_one = |n: usize| n + 1;
+
+        let eval = |sp: StreamProcessor<'static, (usize, usize), (usize, usize)>| {
+            sp.eval_iter(INPUT).collect::<Vec<_>>()
+        };
+
+        // first (arr f) = arr (first f)
+        assert_eq!(
+            eval(first(map(plus_one))),
+            eval(map(move |(a, c)| (plus_one(a), c)))
+        );
+
+        // first (f >>> g) = first f >>> first g
+        assert_eq!(
+            eval(first(compose(filter(is_even), map(plus_one)))),
+            eval(compose(first(filter(is_even)), first(map(plus_one))))
+        );
+
+        // first f >>> arr fst = arr fst >>> f
+        let lhs: StreamProcessor<_, _> =
+            compose(first(filter(is_even)), map(|(b, _): (usize, usize)| b));
+        let rhs: StreamProcessor<_, _> = compose(map(|(a, _): (usize, usize)| a), filter(is_even));
+        assert_eq!(
+            lhs.eval_iter(INPUT).collect::<Vec<_>>(),
+            rhs.eval_iter(INPUT).collect::<Vec<_>>()
+        );
+
+        // first f >>> arr (id *** g) = arr (id *** g) >>> first f
+        assert_eq!(
+            eval(compose(
+                first(filter(is_even)),
+                map(move |(b, c)| (b, plus_one(c)))
+            )),
+            eval(compose(
+                map(move |(a, c)| (a, plus_one(c))),
+                first(filter(is_even))
+            ))
+        );
+
+        // first (first f) >>> arr assoc = arr assoc >>> first f
+        let nested = INPUT.map(|(a, c)| ((a, c), c));
+        let lhs: StreamProcessor<_, _> = compose(
+            first(first(filter(is_even))),
+            map(|((b, c), d)| (b, (c, d))),
+        );
+        let rhs: StreamProcessor<_, _> =
+            compose(map(|((a, c), d)| (a, (c, d))), first(filter(is_even)));
+        assert_eq!(
+            lhs.eval_iter(nested).collect::<Vec<_>>(),
+            rhs.eval_iter(nested).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_fold_every() {
+        let sum = |acc: usize, n: usize| acc + n;
+
+        let sp: StreamProcessor<_, _> = fold_every(2, 0, sum);
+        let result = sp.eval_iter(1..=5).collect::<Vec<_>>();
+        assert_eq!(result, vec![3, 7]);
+
+        let sp: StreamProcessor<_, _> = fold_every(1, 10, sum);
+        let result = sp.eval_iter(1..=3).collect::<Vec<_>>();
+        assert_eq!(result, vec![11, 12, 13]);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunks must not be empty")]
+    fn test_fold_every_empty() {
+        let _: StreamProcessor<_, _> = fold_every(0, 0, |acc: usize, n: usize| acc + n);
+    }
+
     #[test]
     fn test_generate() {
         let ascending = |n: usize| (n, n + 1);
@@ -305,6 +1461,60 @@ mod tests {
         assert_tail_starts_with!(result, [11]);
     }
 
+    #[test]
+    fn test_left() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp: StreamProcessor<_, _> = left(filter(is_greater_zero));
+
+        let result = sp
+            .eval_iter([Either::Left(0), Either::Right('a'), Either::Left(1)])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![Either::Right('a'), Either::Left(1)]);
+    }
+
+    #[test]
+    fn test_left_laws() {
+        const INPUT: [Either<usize, usize>; 4] = [
+            Either::Left(0),
+            Either::Right(1),
+            Either::Left(2),
+            Either::Left(3),
+        ];
+
+        #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+        let is_even = |n: &usize| *n % 2 == 0;
+        let plus_one = |n: usize| n + 1;
+        let tag_left = |n: usize| Either::<usize, usize>::Left(n);
+
+        let eval = |sp: StreamProcessor<'static, Either<usize, usize>, Either<usize, usize>>| {
+            sp.eval_iter(INPUT).collect::<Vec<_>>()
+        };
+
+        // left (arr f) = arr (left f)
+        assert_eq!(
+            eval(left(map(plus_one))),
+            eval(map(move |e| match e {
+                Either::Left(a) => Either::Left(plus_one(a)),
+                Either::Right(c) => Either::Right(c),
+            }))
+        );
+
+        // left (f >>> g) = left f >>> left g
+        assert_eq!(
+            eval(left(compose(filter(is_even), map(plus_one)))),
+            eval(compose(left(filter(is_even)), left(map(plus_one))))
+        );
+
+        // f >>> arr Left = arr Left >>> left f
+        let lhs: StreamProcessor<_, _> = compose(filter(is_even), map(tag_left));
+        let rhs: StreamProcessor<_, _> = compose(map(tag_left), left(filter(is_even)));
+        assert_eq!(
+            lhs.eval_iter([0, 1, 2]).collect::<Vec<_>>(),
+            rhs.eval_iter([0, 1, 2]).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_map() {
         let plus_one = |n: usize| n + 1;
@@ -318,4 +1528,200 @@ mod tests {
         assert_head_eq!(result, 1);
         assert_tail_starts_with!(result, [2]);
     }
+
+    #[test]
+    fn test_mealy() {
+        // This machine writes the inputs in pairs of two.
+        let pairs = |last: Option<usize>, n: usize| match last {
+            None => (Some(n), None),
+            Some(m) => (None, Some((m, n))),
+        };
+
+        let sp: StreamProcessor<_, _> = mealy(None, pairs);
+        let result = sp.eval_iter(0..5).collect::<Vec<_>>();
+        assert_eq!(result, vec![(0, 1), (2, 3)]);
+    }
+
+    #[test]
+    fn test_moore() {
+        #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+        let parity = |b: bool, n: usize| b ^ (n % 2 == 0);
+
+        let sp: StreamProcessor<_, _> = moore(false, parity, |b: &bool| *b);
+        let mut runner = PushRunner::new(sp);
+        assert_eq!(runner.pending().collect::<Vec<_>>(), vec![false]);
+        assert_eq!(runner.feed(2).collect::<Vec<_>>(), vec![true]);
+        assert_eq!(runner.feed(1).collect::<Vec<_>>(), vec![true]);
+        assert_eq!(runner.feed(0).collect::<Vec<_>>(), vec![false]);
+
+        let sp: StreamProcessor<_, _> = moore(false, parity, |b: &bool| *b);
+        let result = sp.eval_iter([1, 2, 2]).collect::<Vec<_>>();
+        assert_eq!(result, vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn test_right() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp: StreamProcessor<_, _> = right(filter(is_greater_zero));
+
+        let result = sp
+            .eval_iter([Either::Right(0), Either::Left('a'), Either::Right(1)])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![Either::Left('a'), Either::Right(1)]);
+    }
+
+    #[test]
+    fn test_scan() {
+        let maximum = |acc: usize, n: usize| usize::max(acc, n);
+
+        let sp: StreamProcessor<_, _> = scan(0, maximum);
+        let result = sp.eval_iter([1, 3, 2, 4]).collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 3, 3, 4]);
+    }
+
+    #[test]
+    fn test_second() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp: StreamProcessor<_, _> = second(filter(is_greater_zero));
+
+        let result = sp
+            .eval_iter([('a', 0), ('b', 1), ('c', 2)])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![('a', 1), ('b', 2)]);
+    }
+
+    #[test]
+    fn test_skip_until() {
+        let is_zero = |n: &usize| *n == 0;
+
+        let sp: StreamProcessor<_, _> = skip_until(is_zero);
+        let result = sp.eval_iter([1, 2, 0, 3, 0]).collect::<Vec<_>>();
+        assert_eq!(result, vec![3, 0]);
+
+        let sp: StreamProcessor<_, _> = skip_until(is_zero);
+        let result = sp.eval_iter([1, 2]).collect::<Vec<_>>();
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn test_split() {
+        let sp: StreamProcessor<_, _> = split(map(|n: usize| n + 1), map(|b: bool| !b));
+
+        let result = sp.eval_iter([(0, true), (1, false)]).collect::<Vec<_>>();
+        assert_eq!(result, vec![(1, false), (2, true)]);
+    }
+
+    #[test]
+    fn test_split_laws() {
+        const INPUT: [(usize, usize); 4] = [(0, 1), (1, 2), (2, 3), (3, 4)];
+
+        let plus_one = |n: usize| n + 1;
+        let double = |n: usize| 2 * n;
+        let sum = |acc: usize, n: usize| acc + n;
+
+        let sums = |ns: &[usize]| {
+            ns.iter()
+                .scan(0, |acc, n| {
+                    *acc += n;
+                    Some(*acc)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let firsts = INPUT.map(|(a, _)| a);
+        let seconds = INPUT.map(|(_, c)| c);
+
+        // arr f *** arr g = arr (f *** g)
+        let sp: StreamProcessor<_, _> = split(map(plus_one), map(double));
+        assert_eq!(
+            sp.eval_iter(INPUT).collect::<Vec<_>>(),
+            INPUT.map(|(a, c)| (plus_one(a), double(c)))
+        );
+
+        // f *** g >>> arr fst = arr fst >>> f
+        let sp: StreamProcessor<_, _> = compose(
+            split(scan(0, sum), map(double)),
+            map(|(b, _): (usize, usize)| b),
+        );
+        assert_eq!(sp.eval_iter(INPUT).collect::<Vec<_>>(), sums(&firsts));
+
+        // f *** g >>> arr snd = arr snd >>> g
+        let sp: StreamProcessor<_, _> = compose(
+            split(map(double), scan(0, sum)),
+            map(|(_, d): (usize, usize)| d),
+        );
+        assert_eq!(sp.eval_iter(INPUT).collect::<Vec<_>>(), sums(&seconds));
+
+        // f &&& g = arr (\a -> (f a, g a)) for f and g writing one output per input
+        let sp: StreamProcessor<_, _> = fanout(scan(0, sum), map(double));
+        assert_eq!(
+            sp.eval_iter(firsts).collect::<Vec<_>>(),
+            sums(&firsts)
+                .into_iter()
+                .zip(firsts.map(double))
+                .collect::<Vec<_>>()
+        );
+
+        // f &&& g >>> arr snd = g
+        let sp: StreamProcessor<_, _> = compose(
+            fanout(map(plus_one), scan(0, sum)),
+            map(|(_, c): (usize, usize)| c),
+        );
+        assert_eq!(sp.eval_iter(firsts).collect::<Vec<_>>(), sums(&firsts));
+    }
+
+    #[test]
+    fn test_take() {
+        assert_eq!(take(2).eval_iter(0..).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(take(3).eval_iter([0]).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(take(0).eval_iter(0..).collect::<Vec<usize>>(), vec![]);
+
+        let sp: StreamProcessor<_, _> = bind(take(2), |()| map(|n: usize| 10 * n));
+        let result = sp.eval_iter(1..).take(4).collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 2, 30, 40]);
+    }
+
+    #[test]
+    fn test_take_compose() {
+        let is_greater_zero = |n: &usize| *n > 0;
+        let double = |n: usize| 2 * n;
+
+        let result = compose(take(3), map(double))
+            .eval_iter(0..)
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![0, 2, 4]);
+
+        let result = compose(filter(is_greater_zero), take(2))
+            .eval_iter(0..)
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 2]);
+
+        let is_less_five = |n: &usize| *n < 5;
+
+        let result = compose(
+            compose(map(double), take_while(is_less_five)),
+            filter(is_greater_zero),
+        )
+        .eval_iter(0..)
+        .collect::<Vec<_>>();
+        assert_eq!(result, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_take_while() {
+        let is_less_two = |n: &usize| *n < 2;
+
+        let result = take_while(is_less_two)
+            .eval_iter([0, 1, 2, 1])
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![0, 1]);
+
+        let sp: StreamProcessor<_, _> = bind(take_while(is_less_two), |n| {
+            StreamProcessor::put(10 * n, || map(|n: usize| n))
+        });
+        let result = sp.eval_iter([0, 2, 1]).collect::<Vec<_>>();
+        assert_eq!(result, vec![0, 20, 1]);
+    }
 }
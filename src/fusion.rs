@@ -0,0 +1,355 @@
+//! This module provides fused versions of the combinators [`map`](`crate::combinators::map`), [`filter`](`crate::combinators::filter`) and [`compose`](`crate::combinators::compose`).
+//! Here 'fused' means that chains of those combinators are represented by types instead of closures such that every input runs through the whole chain in one go without allocating intermediate stream processors.
+//! A chain can either be evaluated directly on an input stream or be turned into an ordinary [`StreamProcessor`] to combine it with other stream processors.
+//!
+//! Note that only those three combinators can be fused so far.
+//! Any other combinator has to be applied to the stream processor a chain is turned into and hence ends the fusion at that point.
+
+use super::streams::Stream;
+use super::StreamProcessor;
+
+use core::marker::PhantomData;
+
+/// A characterization of fused chains of combinators turning inputs of type `A` into outputs of type [`Output`](`Pipeline::Output`).
+pub trait Pipeline<A> {
+    /// The type of the outputs of the chain.
+    type Output;
+
+    /// Run an input through the chain returning the output if the input is not filtered out on the way.
+    fn step(&mut self, a: A) -> Option<Self::Output>;
+
+    /// Turn `self` into an ordinary stream processor behaving the same.
+    ///
+    /// Note that the resulting stream processor allocates once per input and output instead of once per input and output for every combinator in the chain.
+    ///
+    /// # Examples
+    ///
+    /// Combining a fused chain with an ordinary stream processor:
+    ///
+    /// ```
+    /// use rspl::combinators::alternate;
+    /// use rspl::fusion::{compose, filter, map, Pipeline};
+    /// use rspl::streams::infinite_lists::InfiniteList;
+    ///
+    /// let chain = compose(filter(|n: &usize| *n > 0), map(|n: usize| n - 1));
+    ///
+    /// let ones = InfiniteList::constant(1);
+    ///
+    /// alternate(chain.into_stream_processor(), rspl::combinators::map(|n| n)).eval(ones);
+    /// ```
+    fn into_stream_processor<'a>(self) -> StreamProcessor<'a, A, Self::Output>
+    where
+        Self: Sized + 'a,
+        A: 'a,
+        Self::Output: 'a,
+    {
+        lower(self)
+    }
+
+    /// Evaluate `self` on an input stream in the same way as [`eval`](`StreamProcessor::eval`) evaluates the corresponding unfused stream processor.
+    /// - `stream` is the input stream.
+    ///
+    /// Note that the function can block the current thread if the respective implementation of [`Stream::tail`] can.
+    /// Moreover, note that the function does not return before the first input has passed the chain.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the chain contains Rust-terms which can panic.
+    /// - the respective implementation of [`Stream::head`] or [`Stream::tail`] can panic.
+    ///
+    /// # Examples
+    ///
+    /// Turning a stream of ones into a stream of zeroes:
+    ///
+    /// ```
+    /// use rspl::fusion::{compose, filter, map, Pipeline};
+    /// use rspl::streams::infinite_lists::InfiniteList;
+    /// use rspl::streams::Stream;
+    ///
+    /// let chain = compose(filter(|n: &usize| *n > 0), map(|n: usize| n - 1));
+    ///
+    /// let zeroes = chain.eval(InfiniteList::cons(0, || InfiniteList::constant(1)));
+    ///
+    /// assert_eq!(*zeroes.head(), 0);
+    /// ```
+    fn eval<S: Stream<A>>(mut self, mut stream: S) -> FusedStream<A, S, Self>
+    where
+        Self: Sized,
+        A: Clone,
+    {
+        loop {
+            if let Some(output) = self.step(stream.head().clone()) {
+                return FusedStream {
+                    phantom: PhantomData,
+                    output,
+                    stream,
+                    pipeline: self,
+                };
+            }
+            stream = stream.tail();
+        }
+    }
+}
+
+/// Turn a chain into an ordinary stream processor.
+/// - `pipeline` is the chain.
+fn lower<'a, A: 'a, P>(mut pipeline: P) -> StreamProcessor<'a, A, P::Output>
+where
+    P: Pipeline<A> + 'a,
+    P::Output: 'a,
+{
+    StreamProcessor::get(move |a| match pipeline.step(a) {
+        Some(b) => StreamProcessor::put(b, || lower(pipeline)),
+        None => lower(pipeline),
+    })
+}
+
+/// [`FusedStream<A, S, P>`] is the output stream of evaluating a chain of type `P` on an input stream of type `S`.
+pub struct FusedStream<A, S, P: Pipeline<A>> {
+    /// the input type is only determined by the stream and the chain
+    phantom: PhantomData<A>,
+    /// current output of the chain
+    output: P::Output,
+    /// input stream with the input the current output stems from as head
+    stream: S,
+    /// chain producing the outputs
+    pipeline: P,
+}
+
+impl<A, S, P> Stream<P::Output> for FusedStream<A, S, P>
+where
+    A: Clone,
+    S: Stream<A>,
+    P: Pipeline<A>,
+{
+    /// Make the current output of the chain the head.
+    fn head(&self) -> &P::Output {
+        &self.output
+    }
+
+    /// Run the tail of the input stream through the chain until there is a next output.
+    ///
+    /// Note that the function can block the current thread if the respective implementation of [`Stream::tail`] of the input stream can.
+    fn tail(mut self) -> Self {
+        loop {
+            self.stream = self.stream.tail();
+            if let Some(output) = self.pipeline.step(self.stream.head().clone()) {
+                self.output = output;
+                return self;
+            }
+        }
+    }
+}
+
+/// [`Map<F>`] is the fused chain applying a closure of type `F` to each input.
+pub struct Map<F> {
+    /// closure to be applied
+    f: F,
+}
+
+impl<A, B, F> Pipeline<A> for Map<F>
+where
+    F: Fn(A) -> B,
+{
+    type Output = B;
+
+    /// Apply the closure to the input.
+    fn step(&mut self, a: A) -> Option<B> {
+        Some((self.f)(a))
+    }
+}
+
+/// [`Filter<P>`] is the fused chain filtering the inputs according to a predicate of type `P`.
+pub struct Filter<P> {
+    /// predicate serving as filter
+    p: P,
+}
+
+impl<A, P> Pipeline<A> for Filter<P>
+where
+    P: Fn(&A) -> bool,
+{
+    type Output = A;
+
+    /// Keep the input if and only if it satisfies the predicate.
+    fn step(&mut self, a: A) -> Option<A> {
+        if (self.p)(&a) {
+            Some(a)
+        } else {
+            None
+        }
+    }
+}
+
+/// [`Compose<P, Q>`] is the fused chain running the inputs through a chain of type `P` first and the results through a chain of type `Q` second.
+pub struct Compose<P, Q> {
+    /// chain run first
+    first: P,
+    /// chain run second
+    second: Q,
+}
+
+impl<A, P, Q> Pipeline<A> for Compose<P, Q>
+where
+    P: Pipeline<A>,
+    Q: Pipeline<P::Output>,
+{
+    type Output = Q::Output;
+
+    /// Run the input through the first chain and the result, if any, through the second chain.
+    fn step(&mut self, a: A) -> Option<Q::Output> {
+        self.first.step(a).and_then(|b| self.second.step(b))
+    }
+}
+
+/// The function combines two fused chains into one running the inputs through the second after the first.
+/// - `first` is the chain run first.
+/// - `second` is the chain run second.
+///
+/// This function is the fused version of [`compose`](`crate::combinators::compose`).
+///
+/// # Examples
+///
+/// Double-negate a stream of bools:
+///
+/// ```
+/// use rspl::fusion::{compose, map, Pipeline};
+/// use rspl::streams::infinite_lists::InfiniteList;
+///
+/// let negate = |b: bool| !b;
+///
+/// let trues = InfiniteList::constant(true);
+///
+/// compose(map(negate), map(negate)).eval(trues);
+/// ```
+pub const fn compose<P, Q>(first: P, second: Q) -> Compose<P, Q> {
+    Compose { first, second }
+}
+
+/// Construct the fused chain which filters the inputs according to a given predicate.
+/// - `p` is the predicate serving as filter.
+///
+/// This function is the fused version of [`filter`](`crate::combinators::filter`).
+///
+/// # Examples
+///
+/// Remove the `0`s from a stream of integers:
+///
+/// ```
+/// use rspl::fusion::{filter, Pipeline};
+/// use rspl::streams::infinite_lists::InfiniteList;
+///
+/// let is_greater_zero = |n: &usize| *n > 0;
+///
+/// let ones = || InfiniteList::constant(1);
+///
+/// filter(is_greater_zero).eval(InfiniteList::cons(0, ones));
+/// ```
+pub const fn filter<P>(p: P) -> Filter<P> {
+    Filter { p }
+}
+
+/// Construct the fused chain which applies a given closure to each input.
+/// - `f` is the closure to be applied.
+///
+/// This function is the fused version of [`map`](`crate::combinators::map`).
+///
+/// # Examples
+///
+/// Negate a stream of bools:
+///
+/// ```
+/// use rspl::fusion::{map, Pipeline};
+/// use rspl::streams::infinite_lists::InfiniteList;
+///
+/// let negate = |b: bool| !b;
+///
+/// let trues = InfiniteList::constant(true);
+///
+/// map(negate).eval(trues);
+/// ```
+pub const fn map<F>(f: F) -> Map<F> {
+    Map { f }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinators;
+    use crate::streams::infinite_lists::InfiniteList;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+
+    use alloc::vec::Vec;
+
+    fn ascending<'a>(n: usize) -> InfiniteList<'a, usize> {
+        InfiniteList::cons(n, move || ascending(n + 1))
+    }
+
+    #[test]
+    fn test_compose() {
+        let plus_one = |n: usize| n + 1;
+
+        let mut result = compose(map(plus_one), map(plus_one)).eval(ascending(0));
+        assert_head_eq!(result, 2);
+        assert_tail_starts_with!(result, [3, 4]);
+    }
+
+    #[test]
+    fn test_eval() {
+        #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+        let is_even = |n: &usize| *n % 2 == 0;
+        let times_three = |n: usize| 3 * n;
+
+        let mut fused =
+            compose(compose(filter(is_even), map(times_three)), filter(is_even)).eval(ascending(0));
+        let mut unfused = combinators::compose(
+            combinators::compose(combinators::filter(is_even), combinators::map(times_three)),
+            combinators::filter(is_even),
+        )
+        .eval(ascending(0));
+
+        for _ in 0..10 {
+            assert_eq!(fused.head(), unfused.head());
+            fused = fused.tail();
+            unfused = unfused.tail();
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let mut result = filter(is_greater_zero).eval(ascending(0));
+        assert_head_eq!(result, 1);
+        assert_tail_starts_with!(result, [2]);
+    }
+
+    #[test]
+    fn test_into_stream_processor() {
+        #[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+        let is_even = |n: &usize| *n % 2 == 0;
+        let plus_one = |n: usize| n + 1;
+
+        let fused = compose(filter(is_even), map(plus_one))
+            .into_stream_processor()
+            .eval_iter(0..10)
+            .collect::<Vec<_>>();
+        let unfused: StreamProcessor<_, _> =
+            combinators::compose(combinators::filter(is_even), combinators::map(plus_one));
+        let unfused = unfused.eval_iter(0..10).collect::<Vec<_>>();
+
+        assert_eq!(fused, unfused);
+    }
+
+    #[test]
+    fn test_map() {
+        let plus_one = |n: usize| n + 1;
+
+        let mut result = map(plus_one).eval(ascending(0));
+        assert_head_eq!(result, 1);
+        assert_tail_starts_with!(result, [2]);
+    }
+}
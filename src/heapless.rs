@@ -0,0 +1,639 @@
+//! This module provides an implementation of rspl which does not need a heap.
+//!
+//! The implementation is based on closure conversion as explained [here (as .md file)](https://github.com/shtsoft/rspl/blob/master/examples/rspl_heapless.md) and [here (as .rs file)](https://github.com/shtsoft/rspl/blob/master/examples/rspl_heapless.rs).
+//! That is, instead of boxing the functions of [`Get`](`StreamProcessor::Get`) and the thunks of [`Put`](`StreamProcessor::Put`), their environments are made explicit by types implementing the traits [`Fun`] and [`LazySP`].
+//! Thereby Rust can statically determine the size of stream processors and the module neither uses the standard library nor an allocator.
+//! The price is that stream processors are harder to write by hand.
+//! So, the module also provides the most important combinators which do the closure conversion for you.
+
+use super::streams::Stream;
+
+use core::marker::PhantomData;
+
+/// [`StreamProcessor<A, B, F, L>`] defines (the syntax of) a language describing the domain of stream processors, that is, terms which can be interpreted to turn streams of type `A` into streams of type `B`.
+/// It is the same language as the one of [`crate::StreamProcessor`] (without termination) but with the functions of type `F` and the thunks of type `L` closure-converted.
+pub enum StreamProcessor<A, B, F, L> {
+    /// This stream processor first reads the `A` from the head of the input stream and subsequently applies its function argument to that element yielding a stream processor (see [`crate::StreamProcessor::Get`]).
+    Get(F, PhantomData<A>),
+    /// This stream processor writes the `B` from its first argument to the output stream and continues with the stream processor its second argument is forced to (see [`crate::StreamProcessor::Put`]).
+    Put(B, L),
+}
+
+/// A characterization of closure-converted functions from `A` to [`StreamProcessor<A, B, F, L>`].
+pub trait Fun<A, B, F, L> {
+    /// Apply `self` to an input.
+    fn apply(self, a: A) -> StreamProcessor<A, B, F, L>;
+}
+
+/// A characterization of closure-converted thunks of [`StreamProcessor<A, B, F, L>`].
+pub trait LazySP<A, B, F, L> {
+    /// Force `self` to a stream processor.
+    fn force(self) -> StreamProcessor<A, B, F, L>;
+}
+
+impl<A, B, F, L> StreamProcessor<A, B, F, L> {
+    /// The same as [`StreamProcessor::Get`] but with the phantom data hidden to make the resulting code less verbose.
+    #[inline]
+    pub const fn get(f: F) -> Self {
+        StreamProcessor::Get(f, PhantomData)
+    }
+}
+
+impl<A, B, F, L> StreamProcessor<A, B, F, L>
+where
+    F: Fun<A, B, F, L>,
+    L: LazySP<A, B, F, L>,
+{
+    /// Evaluate `self` on an input stream essentially implementing a semantic of [`StreamProcessor<A, B, F, L>`] (see [`crate::StreamProcessor::eval`]).
+    /// - `stream` is the input stream.
+    ///
+    /// Note that the function can block the current thread if the respective implementation of [`Stream::tail`] can.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the stream processor contains Rust-terms which can panic.
+    /// - the respective implementation of [`Stream::head`] or [`Stream::tail`] can panic.
+    ///
+    /// # Examples
+    ///
+    /// Negating a stream of `true`s to obtain a stream of `false`s:
+    ///
+    /// ```
+    /// use rspl::heapless::{map, Constant};
+    /// use rspl::streams::Stream;
+    ///
+    /// let falses = map(|b: bool| !b).eval(Constant(true));
+    ///
+    /// assert!(!falses.head());
+    /// ```
+    pub fn eval<S: Stream<A>>(mut self, mut stream: S) -> Eval<A, B, S, F, L>
+    where
+        A: Clone,
+    {
+        // This implementation is the same as the one of `crate::StreamProcessor::eval`.
+        loop {
+            match self {
+                StreamProcessor::Get(f, _) => {
+                    self = f.apply(stream.head().clone());
+                    while let StreamProcessor::Get(f, _) = self {
+                        stream = stream.tail();
+                        self = f.apply(stream.head().clone());
+                    }
+                    continue;
+                }
+                StreamProcessor::Put(b, lazy_sp) => {
+                    return Eval {
+                        phantom: PhantomData,
+                        stream,
+                        output: b,
+                        lazy_sp,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// [`Eval<A, B, S, F, L>`] is the output stream of evaluating a [`StreamProcessor<A, B, F, L>`] on an input stream of type `S`.
+pub struct Eval<A, B, S, F, L> {
+    /// the types of the input and the functions are only determined by the stream processor
+    phantom: PhantomData<(A, F)>,
+    /// rest of the input stream
+    stream: S,
+    /// current output
+    output: B,
+    /// thunk of the stream processor producing the rest of the output
+    lazy_sp: L,
+}
+
+impl<A, B, S, F, L> Stream<B> for Eval<A, B, S, F, L>
+where
+    A: Clone,
+    S: Stream<A>,
+    F: Fun<A, B, F, L>,
+    L: LazySP<A, B, F, L>,
+{
+    /// Make the current output the head.
+    fn head(&self) -> &B {
+        &self.output
+    }
+
+    /// Force the thunk of the stream processor and evaluate it on the rest of the input stream.
+    fn tail(mut self) -> Self {
+        let sp = self.lazy_sp.force();
+
+        if let StreamProcessor::Get(_, _) = sp {
+            self.stream = self.stream.tail();
+        }
+
+        sp.eval(self.stream)
+    }
+}
+
+/// [`Constant<X>`] defines streams which repeat an element of type `X` forever.
+/// They serve as input streams which do not need a heap, for example, for stream processors which ignore their input like [`generate`] does.
+pub struct Constant<X>(pub X);
+
+impl<X> Stream<X> for Constant<X> {
+    /// Make the repeated element the head.
+    fn head(&self) -> &X {
+        &self.0
+    }
+
+    /// Make `self` the tail as it does not change.
+    fn tail(self) -> Self {
+        self
+    }
+}
+
+/// [`AlternateGet<A, B, F1, L1, F2, L2>`] is the closure-converted function of alternating stream processors which is waiting for input.
+pub enum AlternateGet<A, B, F1, L1, F2, L2> {
+    /// The first stream processor is in control.
+    First(F1, StreamProcessor<A, B, F2, L2>),
+    /// The second stream processor is in control.
+    Second(F2, StreamProcessor<A, B, F1, L1>),
+}
+
+/// [`AlternatePut<A, B, F1, L1, F2, L2>`] is the closure-converted thunk of alternating stream processors which have just written something.
+pub enum AlternatePut<A, B, F1, L1, F2, L2> {
+    /// The first stream processor has written and control goes to the second.
+    First(L1, StreamProcessor<A, B, F2, L2>),
+    /// The second stream processor has written and control goes to the first.
+    Second(L2, StreamProcessor<A, B, F1, L1>),
+}
+
+/// [`Alternate<A, B, F1, L1, F2, L2>`] types the stream processors constructed by [`alternate`].
+pub type Alternate<A, B, F1, L1, F2, L2> =
+    StreamProcessor<A, B, AlternateGet<A, B, F1, L1, F2, L2>, AlternatePut<A, B, F1, L1, F2, L2>>;
+
+impl<A, B, F1, L1, F2, L2>
+    Fun<A, B, AlternateGet<A, B, F1, L1, F2, L2>, AlternatePut<A, B, F1, L1, F2, L2>>
+    for AlternateGet<A, B, F1, L1, F2, L2>
+where
+    F1: Fun<A, B, F1, L1>,
+    F2: Fun<A, B, F2, L2>,
+{
+    /// Let the stream processor in control read the input.
+    fn apply(self, a: A) -> Alternate<A, B, F1, L1, F2, L2> {
+        match self {
+            Self::First(f1, sp2) => alternate(f1.apply(a), sp2),
+            Self::Second(f2, sp1) => alternate_second(f2.apply(a), sp1),
+        }
+    }
+}
+
+impl<A, B, F1, L1, F2, L2>
+    LazySP<A, B, AlternateGet<A, B, F1, L1, F2, L2>, AlternatePut<A, B, F1, L1, F2, L2>>
+    for AlternatePut<A, B, F1, L1, F2, L2>
+where
+    L1: LazySP<A, B, F1, L1>,
+    L2: LazySP<A, B, F2, L2>,
+{
+    /// Transfer control to the other stream processor.
+    fn force(self) -> Alternate<A, B, F1, L1, F2, L2> {
+        match self {
+            Self::First(lazy_sp1, sp2) => alternate_second(sp2, lazy_sp1.force()),
+            Self::Second(lazy_sp2, sp1) => alternate(sp1, lazy_sp2.force()),
+        }
+    }
+}
+
+/// The function combines two stream processors into one alternating between the two whenever something is written to the ouput stream.
+/// - `sp1` is the stream processor which is in control.
+/// - `sp2` is the stream processor to which control is transferred.
+///
+/// This function is the heapless version of [`crate::combinators::alternate`].
+///
+/// # Examples
+///
+/// Negate a stream of bools in every other position:
+///
+/// ```
+/// use rspl::heapless::{alternate, map, Constant};
+///
+/// let id = |b: bool| b;
+/// let negate = |b: bool| !b;
+///
+/// alternate(map(id), map(negate)).eval(Constant(true));
+/// ```
+pub fn alternate<A, B, F1, L1, F2, L2>(
+    sp1: StreamProcessor<A, B, F1, L1>,
+    sp2: StreamProcessor<A, B, F2, L2>,
+) -> Alternate<A, B, F1, L1, F2, L2> {
+    match sp1 {
+        StreamProcessor::Get(f1, _) => StreamProcessor::get(AlternateGet::First(f1, sp2)),
+        StreamProcessor::Put(b, lazy_sp1) => {
+            StreamProcessor::Put(b, AlternatePut::First(lazy_sp1, sp2))
+        }
+    }
+}
+
+/// The function is the same as [`alternate`] but with the second stream processor in control.
+/// - `sp2` is the stream processor which is in control.
+/// - `sp1` is the stream processor to which control is transferred.
+fn alternate_second<A, B, F1, L1, F2, L2>(
+    sp2: StreamProcessor<A, B, F2, L2>,
+    sp1: StreamProcessor<A, B, F1, L1>,
+) -> Alternate<A, B, F1, L1, F2, L2> {
+    match sp2 {
+        StreamProcessor::Get(f2, _) => StreamProcessor::get(AlternateGet::Second(f2, sp1)),
+        StreamProcessor::Put(b, lazy_sp2) => {
+            StreamProcessor::Put(b, AlternatePut::Second(lazy_sp2, sp1))
+        }
+    }
+}
+
+/// [`ComposeGet<A, B, C, F1, L1, F2, L2>`] is the closure-converted function of composed stream processors which are waiting for input.
+pub struct ComposeGet<A, B, C, F1, L1, F2, L2> {
+    /// the types of the input and the thunks of the first stream processor are only determined by the function
+    phantom: PhantomData<(A, L1)>,
+    /// function of the first stream processor
+    f1: F1,
+    /// second stream processor
+    sp2: StreamProcessor<B, C, F2, L2>,
+}
+
+/// [`ComposePut<A, B, C, F1, L1, F2, L2>`] is the closure-converted thunk of composed stream processors which have just written something.
+pub struct ComposePut<A, B, C, F1, L1, F2, L2> {
+    /// the types of the output and the functions of the second stream processor are only determined by the thunk
+    phantom: PhantomData<(C, F2)>,
+    /// first stream processor
+    sp1: StreamProcessor<A, B, F1, L1>,
+    /// thunk of the second stream processor
+    lazy_sp2: L2,
+}
+
+/// [`Compose<A, B, C, F1, L1, F2, L2>`] types the stream processors constructed by [`compose`].
+pub type Compose<A, B, C, F1, L1, F2, L2> =
+    StreamProcessor<A, C, ComposeGet<A, B, C, F1, L1, F2, L2>, ComposePut<A, B, C, F1, L1, F2, L2>>;
+
+impl<A, B, C, F1, L1, F2, L2>
+    Fun<A, C, ComposeGet<A, B, C, F1, L1, F2, L2>, ComposePut<A, B, C, F1, L1, F2, L2>>
+    for ComposeGet<A, B, C, F1, L1, F2, L2>
+where
+    F1: Fun<A, B, F1, L1>,
+    L1: LazySP<A, B, F1, L1>,
+    F2: Fun<B, C, F2, L2>,
+    L2: LazySP<B, C, F2, L2>,
+{
+    /// Let the first stream processor read the input.
+    fn apply(self, a: A) -> Compose<A, B, C, F1, L1, F2, L2> {
+        compose(self.f1.apply(a), self.sp2)
+    }
+}
+
+impl<A, B, C, F1, L1, F2, L2>
+    LazySP<A, C, ComposeGet<A, B, C, F1, L1, F2, L2>, ComposePut<A, B, C, F1, L1, F2, L2>>
+    for ComposePut<A, B, C, F1, L1, F2, L2>
+where
+    F1: Fun<A, B, F1, L1>,
+    L1: LazySP<A, B, F1, L1>,
+    F2: Fun<B, C, F2, L2>,
+    L2: LazySP<B, C, F2, L2>,
+{
+    /// Force the thunk of the second stream processor.
+    fn force(self) -> Compose<A, B, C, F1, L1, F2, L2> {
+        compose(self.sp1, self.lazy_sp2.force())
+    }
+}
+
+/// The function combines two stream processors into one applying the second to the result of the first.
+/// - `sp1` is the stream processor applied first.
+/// - `sp2` is the stream processor applied second.
+///
+/// This function is the heapless version of [`crate::combinators::compose`].
+///
+/// # Examples
+///
+/// Double-negate a stream of bools:
+///
+/// ```
+/// use rspl::heapless::{compose, map, Constant};
+///
+/// let negate = |b: bool| !b;
+///
+/// compose(map(negate), map(negate)).eval(Constant(true));
+/// ```
+pub fn compose<A, B, C, F1, L1, F2, L2>(
+    mut sp1: StreamProcessor<A, B, F1, L1>,
+    mut sp2: StreamProcessor<B, C, F2, L2>,
+) -> Compose<A, B, C, F1, L1, F2, L2>
+where
+    F1: Fun<A, B, F1, L1>,
+    L1: LazySP<A, B, F1, L1>,
+    F2: Fun<B, C, F2, L2>,
+    L2: LazySP<B, C, F2, L2>,
+{
+    loop {
+        match (sp1, sp2) {
+            (sp1, StreamProcessor::Put(c, lazy_sp2)) => {
+                return StreamProcessor::Put(
+                    c,
+                    ComposePut {
+                        phantom: PhantomData,
+                        sp1,
+                        lazy_sp2,
+                    },
+                )
+            }
+            (StreamProcessor::Get(f1, _), sp2) => {
+                return StreamProcessor::get(ComposeGet {
+                    phantom: PhantomData,
+                    f1,
+                    sp2,
+                })
+            }
+            (StreamProcessor::Put(b, lazy_sp1), StreamProcessor::Get(f2, _)) => {
+                sp1 = lazy_sp1.force();
+                sp2 = f2.apply(b);
+                continue;
+            }
+        }
+    }
+}
+
+/// [`FilterClosure<A, P>`] is the closure-converted function and thunk of the stream processor filtering according to a predicate of type `P`.
+pub struct FilterClosure<A, P> {
+    /// the input type is only determined by the predicate
+    phantom: PhantomData<fn(&A)>,
+    /// predicate serving as filter
+    p: P,
+}
+
+/// [`Filter<A, P>`] types the stream processors constructed by [`filter`].
+pub type Filter<A, P> = StreamProcessor<A, A, FilterClosure<A, P>, FilterClosure<A, P>>;
+
+impl<A, P> Fun<A, A, FilterClosure<A, P>, FilterClosure<A, P>> for FilterClosure<A, P>
+where
+    P: Fn(&A) -> bool,
+{
+    /// Write the input if it satisfies the predicate and read the next input otherwise.
+    fn apply(self, a: A) -> Filter<A, P> {
+        if (self.p)(&a) {
+            StreamProcessor::Put(a, self)
+        } else {
+            StreamProcessor::get(self)
+        }
+    }
+}
+
+impl<A, P> LazySP<A, A, FilterClosure<A, P>, FilterClosure<A, P>> for FilterClosure<A, P>
+where
+    P: Fn(&A) -> bool,
+{
+    /// Read the next input.
+    fn force(self) -> Filter<A, P> {
+        StreamProcessor::get(self)
+    }
+}
+
+/// Construct the stream processor which filters the input stream according to a given predicate.
+/// - `p` is the predicate serving as filter.
+///
+/// This function is the heapless version of [`crate::combinators::filter`].
+///
+/// # Examples
+///
+/// Remove the `0`s from a stream of integers:
+///
+/// ```
+/// use rspl::heapless::{filter, generate, Constant};
+///
+/// let is_greater_zero = |n: &usize| *n > 0;
+/// let int_mod_3 = |n: usize| (n % 3, n + 1);
+///
+/// let ints_mod_3 = generate(int_mod_3, 0).eval(Constant(()));
+///
+/// filter(is_greater_zero).eval(ints_mod_3);
+/// ```
+pub const fn filter<A, P>(p: P) -> Filter<A, P>
+where
+    P: Fn(&A) -> bool,
+{
+    StreamProcessor::get(FilterClosure {
+        phantom: PhantomData,
+        p,
+    })
+}
+
+/// [`GenerateClosure<A, B, S, F>`] is the closure-converted function and thunk of the stream processor generating output from a state of type `S` by a function of type `F`.
+pub struct GenerateClosure<A, B, S, F> {
+    /// the types of in- and output are only determined by the function
+    phantom: PhantomData<fn(A) -> B>,
+    /// function producing the output in state-passing style
+    body: F,
+    /// current state
+    state: S,
+}
+
+/// [`Generate<A, B, S, F>`] types the stream processors constructed by [`generate`].
+pub type Generate<A, B, S, F> =
+    StreamProcessor<A, B, GenerateClosure<A, B, S, F>, GenerateClosure<A, B, S, F>>;
+
+impl<A, B, S, F> Fun<A, B, GenerateClosure<A, B, S, F>, GenerateClosure<A, B, S, F>>
+    for GenerateClosure<A, B, S, F>
+where
+    F: Fn(S) -> (B, S),
+{
+    /// Ignore the input and generate the next output (which never happens as generating never reads).
+    fn apply(self, _: A) -> Generate<A, B, S, F> {
+        self.force()
+    }
+}
+
+impl<A, B, S, F> LazySP<A, B, GenerateClosure<A, B, S, F>, GenerateClosure<A, B, S, F>>
+    for GenerateClosure<A, B, S, F>
+where
+    F: Fn(S) -> (B, S),
+{
+    /// Generate the next output.
+    fn force(self) -> Generate<A, B, S, F> {
+        generate(self.body, self.state)
+    }
+}
+
+/// Construct the stream processor which produces an output without reading from the input according to a function remembering its state.
+/// - `body` is the function producing the output in state-passing style.
+/// - `state` is the initial state.
+///
+/// This function is the heapless version of [`crate::combinators::generate`].
+///
+/// # Examples
+///
+/// Generate the stream `0, 1, 2, 0, 1, 2, 0, ...`:
+///
+/// ```
+/// use rspl::heapless::{generate, Constant};
+///
+/// let int_mod_3 = |n: usize| (n % 3, n + 1);
+///
+/// generate(int_mod_3, 0).eval(Constant(()));
+/// ```
+pub fn generate<A, B, S, F>(body: F, state: S) -> Generate<A, B, S, F>
+where
+    F: Fn(S) -> (B, S),
+{
+    let (x, state) = body(state);
+    StreamProcessor::Put(
+        x,
+        GenerateClosure {
+            phantom: PhantomData,
+            body,
+            state,
+        },
+    )
+}
+
+/// [`MapClosure<A, B, F>`] is the closure-converted function and thunk of the stream processor applying a closure of type `F`.
+pub struct MapClosure<A, B, F> {
+    /// the types of in- and output are only determined by the closure
+    phantom: PhantomData<fn(A) -> B>,
+    /// closure to be applied
+    f: F,
+}
+
+/// [`Map<A, B, F>`] types the stream processors constructed by [`map`].
+pub type Map<A, B, F> = StreamProcessor<A, B, MapClosure<A, B, F>, MapClosure<A, B, F>>;
+
+impl<A, B, F> Fun<A, B, MapClosure<A, B, F>, MapClosure<A, B, F>> for MapClosure<A, B, F>
+where
+    F: Fn(A) -> B,
+{
+    /// Write the result of applying the closure to the input.
+    fn apply(self, a: A) -> Map<A, B, F> {
+        StreamProcessor::Put((self.f)(a), self)
+    }
+}
+
+impl<A, B, F> LazySP<A, B, MapClosure<A, B, F>, MapClosure<A, B, F>> for MapClosure<A, B, F>
+where
+    F: Fn(A) -> B,
+{
+    /// Read the next input.
+    fn force(self) -> Map<A, B, F> {
+        StreamProcessor::get(self)
+    }
+}
+
+/// Construct the stream processor which applies a given closure to each element of the input stream.
+/// - `f` is the closure to be applied.
+///
+/// This function is the heapless version of [`crate::combinators::map`].
+///
+/// # Examples
+///
+/// Negate a stream of bools:
+///
+/// ```
+/// use rspl::heapless::{map, Constant};
+///
+/// let negate = |b: bool| !b;
+///
+/// map(negate).eval(Constant(true));
+/// ```
+pub const fn map<A, B, F>(f: F) -> Map<A, B, F>
+where
+    F: Fn(A) -> B,
+{
+    StreamProcessor::get(MapClosure {
+        phantom: PhantomData,
+        f,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+
+    /// [`Inputs<X>`] defines streams which repeat the elements of a slice forever without needing a heap.
+    struct Inputs<X: 'static> {
+        /// repeated elements
+        xs: &'static [X],
+        /// position of the head in the slice
+        i: usize,
+    }
+
+    impl<X> Inputs<X> {
+        const fn new(xs: &'static [X]) -> Self {
+            Self { xs, i: 0 }
+        }
+    }
+
+    impl<X> Stream<X> for Inputs<X> {
+        fn head(&self) -> &X {
+            &self.xs[self.i % self.xs.len()]
+        }
+
+        fn tail(mut self) -> Self {
+            self.i += 1;
+            self
+        }
+    }
+
+    #[test]
+    fn test_alternate() {
+        let is_greater_zero = |n: &i8| *n > 0;
+        let is_less_zero = |n: &i8| *n < 0;
+
+        let sp = alternate(filter(is_greater_zero), filter(is_less_zero));
+
+        let mut result = sp.eval(Inputs::new(&[1, 2, -1, -2, 1]));
+        assert_head_eq!(result, 1);
+        assert_tail_starts_with!(result, [-1, 1]);
+    }
+
+    #[test]
+    fn test_compose() {
+        let plus_one = |n: usize| n + 1;
+
+        let sp = compose(map(plus_one), map(plus_one));
+
+        let mut result = sp.eval(Inputs::new(&[0, 1, 2]));
+        assert_head_eq!(result, 2);
+        assert_tail_starts_with!(result, [3, 4]);
+    }
+
+    #[test]
+    fn test_constant() {
+        let mut stream = Constant(true);
+        assert_head_eq!(stream, true);
+        assert_tail_starts_with!(stream, [true, true]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp = filter(is_greater_zero);
+
+        let mut result = sp.eval(Inputs::new(&[0, 1, 0, 2]));
+        assert_head_eq!(result, 1);
+        assert_tail_starts_with!(result, [2]);
+    }
+
+    #[test]
+    fn test_generate() {
+        let ascending = |n: usize| (n, n + 1);
+
+        let sp = generate(ascending, 10);
+
+        let mut result = sp.eval(Constant(()));
+        assert_head_eq!(result, 10);
+        assert_tail_starts_with!(result, [11]);
+    }
+
+    #[test]
+    fn test_map() {
+        let plus_one = |n: usize| n + 1;
+
+        let sp = map(plus_one);
+
+        let mut result = sp.eval(Inputs::new(&[0, 1]));
+        assert_head_eq!(result, 1);
+        assert_tail_starts_with!(result, [2]);
+    }
+}
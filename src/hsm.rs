@@ -0,0 +1,1432 @@
+//! This module provides hierarchical state machines which are described as data and lowered to stream processors afterwards.
+//!
+//! In contrast to the machines of the [`machines`](`crate::machines`)-module, the states of an [`Hsm`] are organized in a tree: every state can have substates which inherit its transitions.
+//! Moreover, states can have entry and exit outputs written whenever a transition enters or exits them, transitions can be guarded by arbitrary predicates on the input, and states with substates can remember their last active substate (history).
+//!
+//! The semantics follows the one of UML statecharts:
+//! - The machine is always in a leaf state together with all of its ancestors.
+//! - On an input, the transitions are looked up starting from the leaf state and walking up to the root, taking the first transition whose guard accepts the input.
+//! - Taking a transition writes the exit outputs of the exited states (innermost first), then the outputs of the transition and then the entry outputs of the entered states (outermost first).
+//! - Entering a state with substates enters its initial substate (the one added first) unless the state has a history.
+//!
+//! This is the same kind of machine the pelican example encodes by hand with nested functions.
+//! But other than there, an [`Hsm`] can be drawn by exporting it to [Graphviz](https://graphviz.org) or [Mermaid](https://mermaid.js.org).
+
+use super::closures::box_fn;
+use super::machines::{escape_dot, escape_mermaid, list_states, puts, state_id, trigger_label};
+use super::StreamProcessor;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+
+/// [`History`] determines which substate is entered when re-entering a state with substates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum History {
+    /// Enter the substate which was active when the state was exited last and enter its substates as usual.
+    Shallow,
+    /// Enter the leaf state which was active when the state was exited last together with all states in between.
+    Deep,
+}
+
+/// [`Node<S, B>`] describes a state of type `S` of a hierarchical state machine writing outputs of type `B`.
+struct Node<S, B> {
+    /// the state itself
+    state: S,
+    /// parent of the state if the state is not top-level
+    parent: Option<S>,
+    /// substates of the state where the first one is the initial one
+    substates: Vec<S>,
+    /// kind of history the state remembers if any
+    history: Option<History>,
+    /// outputs written on entering the state
+    entry: Vec<B>,
+    /// outputs written on exiting the state
+    exit: Vec<B>,
+}
+
+/// [`Trigger<A>`] describes which inputs of type `A` trigger a transition.
+enum Trigger<'a, A> {
+    /// inputs equal to a certain input w.r.t. some equality
+    Input(A, fn(&A, &A) -> bool),
+    /// inputs satisfying a guard with an optional description
+    Guard(Box<dyn Fn(&A) -> bool + 'a>, Option<String>),
+}
+
+impl<A> Trigger<'_, A> {
+    /// Decide whether an input triggers the transition.
+    /// - `a` is the input.
+    fn accepts(&self, a: &A) -> bool {
+        match self {
+            Self::Input(input, eq) => eq(input, a),
+            Self::Guard(guard, _) => guard(a),
+        }
+    }
+
+    /// Describe the trigger for the exports.
+    fn label(&self) -> String
+    where
+        A: fmt::Debug,
+    {
+        match self {
+            Self::Input(input, _) => format!("{:?}", input),
+            Self::Guard(_, Some(label)) => format!("[{}]", label),
+            Self::Guard(_, None) => String::from("[guard]"),
+        }
+    }
+}
+
+/// [`Rule<S, A, B>`] describes a transition of a hierarchical state machine with states of type `S` reading inputs of type `A` and writing outputs of type `B`.
+struct Rule<'a, S, A, B> {
+    /// state the transition starts in
+    source: S,
+    /// inputs triggering the transition
+    trigger: Trigger<'a, A>,
+    /// state the transition ends in or `None` if the transition is internal
+    target: Option<S>,
+    /// outputs written when taking the transition
+    outputs: Vec<B>,
+}
+
+/// [`Configuration<S>`] is the runtime state of a hierarchical state machine with states of type `S`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Configuration<S> {
+    /// active leaf state
+    state: S,
+    /// remembered substates of the states with history
+    history: Vec<(S, S)>,
+}
+
+impl<S> Configuration<S> {
+    /// Return the active leaf state.
+    pub const fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Look up the substate remembered for a state.
+    /// - `state` is the state.
+    fn remembered(&self, state: &S) -> Option<&S>
+    where
+        S: PartialEq,
+    {
+        self.history
+            .iter()
+            .find(|(s, _)| s == state)
+            .map(|(_, substate)| substate)
+    }
+
+    /// Remember a substate for a state overwriting what was remembered before.
+    /// - `state` is the state.
+    /// - `substate` is the substate to be remembered.
+    fn remember(&mut self, state: S, substate: S)
+    where
+        S: PartialEq,
+    {
+        match self.history.iter_mut().find(|(s, _)| *s == state) {
+            Some((_, remembered)) => *remembered = substate,
+            None => self.history.push((state, substate)),
+        }
+    }
+}
+
+/// [`Hsm<S, A, B>`] describes a hierarchical state machine with states of type `S` reading inputs of type `A` and writing outputs of type `B`.
+///
+/// States which are never declared as substates are top-level states.
+/// See the [module documentation](`crate::hsm`) for the semantics.
+pub struct Hsm<'a, S, A, B> {
+    /// state the machine starts in
+    initial: S,
+    /// states with their hierarchy, history and entry and exit outputs
+    nodes: Vec<Node<S, B>>,
+    /// transitions where transitions added before take precedence
+    rules: Vec<Rule<'a, S, A, B>>,
+}
+
+impl<'a, S, A, B> Hsm<'a, S, A, B>
+where
+    S: PartialEq + Clone,
+{
+    /// Create a machine without hierarchy, outputs and transitions.
+    /// - `initial` is the state the machine starts in.
+    ///
+    /// If the initial state has substates, the machine starts in its initial substate (recursively).
+    ///
+    /// # Examples
+    ///
+    /// Creating a machine which stays in its initial state forever:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm: Hsm<(), bool, bool> = Hsm::new(());
+    /// ```
+    pub const fn new(initial: S) -> Self {
+        Self {
+            initial,
+            nodes: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Declare a state to be a substate of another state.
+    /// - `parent` is the other state.
+    /// - `state` is the state.
+    ///
+    /// The first substate declared for a state is its initial substate.
+    ///
+    /// # Panics
+    ///
+    /// A panic occurs if
+    /// - `state` is already a substate of some state.
+    /// - `state` is `parent` or one of its ancestors as that would make the hierarchy cyclic.
+    ///
+    /// # Examples
+    ///
+    /// Letting a light be off or on while being operational:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm: Hsm<&str, (), ()> = Hsm::new("operational")
+    ///     .substate("operational", "off")
+    ///     .substate("operational", "on");
+    /// ```
+    pub fn substate(mut self, parent: S, state: S) -> Self {
+        assert!(
+            parent != state,
+            "states must not be substates of themselves"
+        );
+        assert!(
+            !self.lineage(&parent).contains(&&state),
+            "states must not be substates of their substates"
+        );
+
+        let node = self.node_mut(state.clone());
+        assert!(node.parent.is_none(), "states must have at most one parent");
+        node.parent = Some(parent.clone());
+
+        self.node_mut(parent).substates.push(state);
+        self
+    }
+
+    /// Let a state with substates remember its last active substate.
+    /// - `state` is the state.
+    /// - `history` is the kind of history.
+    ///
+    /// # Examples
+    ///
+    /// Letting a light remember whether it was off or on when it broke down:
+    ///
+    /// ```
+    /// use rspl::hsm::{History, Hsm};
+    ///
+    /// let hsm: Hsm<&str, (), ()> = Hsm::new("operational")
+    ///     .substate("operational", "off")
+    ///     .substate("operational", "on")
+    ///     .history("operational", History::Shallow);
+    /// ```
+    pub fn history(mut self, state: S, history: History) -> Self {
+        self.node_mut(state).history = Some(history);
+        self
+    }
+
+    /// Add outputs to be written on entering a certain state.
+    /// - `state` is the state.
+    /// - `outputs` are the outputs.
+    ///
+    /// Outputs added for the same state before are kept and written first.
+    pub fn entry<T>(mut self, state: S, outputs: T) -> Self
+    where
+        T: IntoIterator<Item = B>,
+    {
+        self.node_mut(state).entry.extend(outputs);
+        self
+    }
+
+    /// Add outputs to be written on exiting a certain state.
+    /// - `state` is the state.
+    /// - `outputs` are the outputs.
+    ///
+    /// Outputs added for the same state before are kept and written first.
+    pub fn exit<T>(mut self, state: S, outputs: T) -> Self
+    where
+        T: IntoIterator<Item = B>,
+    {
+        self.node_mut(state).exit.extend(outputs);
+        self
+    }
+
+    /// Add a transition triggered by a certain input.
+    /// - `source` is the state the transition starts in.
+    /// - `input` is the input triggering the transition.
+    /// - `target` is the state the transition ends in.
+    /// - `outputs` are the outputs written when taking the transition.
+    ///
+    /// Transitions added before take precedence over transitions added later.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on and off by pushing a button:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off")
+    ///     .transition("off", (), "on", [true])
+    ///     .transition("on", (), "off", [false]);
+    /// ```
+    pub fn transition<T>(mut self, source: S, input: A, target: S, outputs: T) -> Self
+    where
+        A: PartialEq,
+        T: IntoIterator<Item = B>,
+    {
+        self.rules.push(Rule {
+            source,
+            trigger: Trigger::Input(input, A::eq),
+            target: Some(target),
+            outputs: outputs.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Add a transition triggered by all inputs satisfying a guard.
+    /// - `source` is the state the transition starts in.
+    /// - `guard` is the predicate deciding whether an input triggers the transition.
+    /// - `target` is the state the transition ends in.
+    /// - `outputs` are the outputs written when taking the transition.
+    ///
+    /// If `target` is `source`, the transition exits and re-enters `source`.
+    /// If `target` is a substate (of a substate ...) of `source`, the transition does not exit `source`.
+    ///
+    /// Transitions added before take precedence over transitions added later.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on with any positive input:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off").transition_if("off", |n: &usize| *n > 0, "on", [true]);
+    /// ```
+    pub fn transition_if<G, T>(mut self, source: S, guard: G, target: S, outputs: T) -> Self
+    where
+        G: Fn(&A) -> bool + 'a,
+        T: IntoIterator<Item = B>,
+    {
+        self.rules.push(Rule {
+            source,
+            trigger: Trigger::Guard(Box::new(guard), None),
+            target: Some(target),
+            outputs: outputs.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Add an internal transition triggered by all inputs satisfying a guard, that is, a transition which writes outputs without exiting or entering any state.
+    /// - `source` is the state the transition belongs to.
+    /// - `guard` is the predicate deciding whether an input triggers the transition.
+    /// - `outputs` are the outputs written when taking the transition.
+    ///
+    /// In particular, internal transitions without outputs can be used to ignore inputs a superstate would react to.
+    ///
+    /// Transitions added before take precedence over transitions added later.
+    ///
+    /// # Examples
+    ///
+    /// Letting a light complain about being pushed while on:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("on").internal("on", |_: &()| true, ["already on"]);
+    /// ```
+    pub fn internal<G, T>(mut self, source: S, guard: G, outputs: T) -> Self
+    where
+        G: Fn(&A) -> bool + 'a,
+        T: IntoIterator<Item = B>,
+    {
+        self.rules.push(Rule {
+            source,
+            trigger: Trigger::Guard(Box::new(guard), None),
+            target: None,
+            outputs: outputs.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Describe the guard of the transition added last by a label which is used by the exports.
+    /// - `label` is the label.
+    ///
+    /// Unlabeled guards are exported as `[guard]`.
+    ///
+    /// # Panics
+    ///
+    /// A panic occurs if the transition added last is not guarded.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on with any positive input:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off")
+    ///     .transition_if("off", |n: &usize| *n > 0, "on", [true])
+    ///     .guard_label("n > 0");
+    ///
+    /// assert!(hsm.to_mermaid().contains("[n > 0] / [true]"));
+    /// ```
+    pub fn guard_label<L>(mut self, label: L) -> Self
+    where
+        L: Into<String>,
+    {
+        let trigger = self.rules.last_mut().map(|rule| &mut rule.trigger);
+        assert!(
+            matches!(trigger, Some(Trigger::Guard(_, _))),
+            "the transition added last must be guarded"
+        );
+
+        if let Some(Trigger::Guard(_, guard_label)) = trigger {
+            *guard_label = Some(label.into());
+        }
+        self
+    }
+
+    /// Return the state the machine starts in.
+    pub const fn initial(&self) -> &S {
+        &self.initial
+    }
+
+    /// Return the parent of a state if it is not top-level.
+    /// - `state` is the state.
+    pub fn parent(&self, state: &S) -> Option<&S> {
+        self.node(state).and_then(|node| node.parent.as_ref())
+    }
+
+    /// Return all states of the machine, that is, the initial state and all states mentioned by the hierarchy, outputs or transitions, in order of appearance and without duplicates.
+    ///
+    /// # Examples
+    ///
+    /// Listing the states of a light:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm: Hsm<&str, (), ()> = Hsm::new("off").transition("off", (), "on", []);
+    ///
+    /// assert_eq!(hsm.states(), vec![&"off", &"on"]);
+    /// ```
+    pub fn states(&self) -> Vec<&S> {
+        let mentioned = self.nodes.iter().map(|node| &node.state).chain(
+            self.rules
+                .iter()
+                .flat_map(|rule| [Some(&rule.source), rule.target.as_ref()])
+                .flatten(),
+        );
+
+        list_states(&self.initial, mentioned)
+    }
+
+    /// Return the substates of a state where the first one is the initial one.
+    /// - `state` is the state.
+    pub fn substates(&self, state: &S) -> &[S] {
+        self.node(state).map_or(&[], |node| &node.substates)
+    }
+
+    /// Decide whether a configuration is in a certain state, that is, whether the state is the active leaf state or one of its ancestors.
+    /// - `configuration` is the configuration.
+    /// - `state` is the state.
+    pub fn is_in(&self, configuration: &Configuration<S>, state: &S) -> bool {
+        self.lineage(&configuration.state).contains(&state)
+    }
+
+    /// Start the machine by entering the initial state.
+    ///
+    /// The result consists of the entry outputs written and the configuration the machine is in afterwards.
+    ///
+    /// # Examples
+    ///
+    /// Starting a light which is off initially:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm: Hsm<_, (), _> = Hsm::new("operational")
+    ///     .substate("operational", "off")
+    ///     .entry("operational", ["ready"])
+    ///     .entry("off", ["dark"]);
+    ///
+    /// let (outputs, configuration) = hsm.start();
+    ///
+    /// assert_eq!(outputs, vec!["ready", "dark"]);
+    /// assert_eq!(configuration.state(), &"off");
+    /// ```
+    pub fn start(&self) -> (Vec<B>, Configuration<S>)
+    where
+        B: Clone,
+    {
+        let mut configuration = Configuration {
+            state: self.initial.clone(),
+            history: Vec::new(),
+        };
+        let mut outputs = Vec::new();
+
+        self.enter(&mut configuration, None, &self.initial, &mut outputs);
+
+        (outputs, configuration)
+    }
+
+    /// Process an input in a certain configuration.
+    /// - `configuration` is the configuration which is updated in place.
+    /// - `input` is the input.
+    ///
+    /// The result is the outputs written or `None` if the input is ignored.
+    ///
+    /// # Examples
+    ///
+    /// Pushing the button of a light in a room:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("room")
+    ///     .substate("room", "off")
+    ///     .substate("room", "on")
+    ///     .exit("off", ["leaving off"])
+    ///     .entry("on", ["entering on"])
+    ///     .transition("off", (), "on", ["click"]);
+    ///
+    /// let (_, mut configuration) = hsm.start();
+    ///
+    /// assert_eq!(
+    ///     hsm.step(&mut configuration, &()),
+    ///     Some(vec!["leaving off", "click", "entering on"])
+    /// );
+    /// assert_eq!(hsm.step(&mut configuration, &()), None);
+    /// ```
+    pub fn step(&self, configuration: &mut Configuration<S>, input: &A) -> Option<Vec<B>>
+    where
+        B: Clone,
+    {
+        let rule = self
+            .lineage(&configuration.state)
+            .into_iter()
+            .find_map(|state| {
+                self.rules
+                    .iter()
+                    .find(|rule| rule.source == *state && rule.trigger.accepts(input))
+            })?;
+
+        let mut outputs = Vec::new();
+        match &rule.target {
+            None => outputs.extend(rule.outputs.iter().cloned()),
+            Some(target) => {
+                let domain = self.domain(&rule.source, target);
+                self.exit_to(configuration, domain, &mut outputs);
+                outputs.extend(rule.outputs.iter().cloned());
+                self.enter(configuration, domain, target, &mut outputs);
+            }
+        }
+        Some(outputs)
+    }
+
+    /// Run the machine on a sequence of inputs without lowering it to a stream processor.
+    /// - `inputs` are the inputs.
+    ///
+    /// The result consists of the outputs written (including the entry outputs written on starting) and the final configuration.
+    pub fn run<T>(&self, inputs: T) -> (Vec<B>, Configuration<S>)
+    where
+        B: Clone,
+        T: IntoIterator<Item = A>,
+    {
+        let (mut outputs, mut configuration) = self.start();
+
+        for input in inputs {
+            if let Some(step) = self.step(&mut configuration, &input) {
+                outputs.extend(step);
+            }
+        }
+
+        (outputs, configuration)
+    }
+
+    /// Lower `self` to a stream processor behaving like the machine.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on and off by pushing a button:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off")
+    ///     .transition("off", (), "on", [true])
+    ///     .transition("on", (), "off", [false]);
+    ///
+    /// let result = hsm.into_stream_processor().eval_iter([(); 3]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(result, vec![true, false, true]);
+    /// ```
+    pub fn into_stream_processor(self) -> StreamProcessor<'a, A, B>
+    where
+        S: 'a,
+        A: 'a,
+        B: Clone + 'a,
+    {
+        let (outputs, configuration) = self.start();
+
+        let hsm = Rc::new(self);
+        puts(outputs, move || lower(hsm, configuration))
+    }
+
+    /// Export `self` as graph in the DOT-language of [Graphviz](https://graphviz.org).
+    ///
+    /// Every state becomes a node and every state with substates additionally a cluster containing the substates.
+    /// The states are labeled with their history, entry outputs, exit outputs and internal transitions and the other transitions become edges labeled with their trigger and outputs.
+    /// Inputs triggering a transition are printed as they are while guards are printed in brackets (see [`guard_label`](`Hsm::guard_label`)).
+    ///
+    /// # Examples
+    ///
+    /// Drawing a light:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off").transition("off", (), "on", [true]);
+    ///
+    /// assert!(hsm.to_dot().contains("s0 -> s1 [label=\"() / [true]\"];"));
+    /// ```
+    pub fn to_dot(&self) -> String
+    where
+        S: fmt::Debug,
+        A: fmt::Debug,
+        B: fmt::Debug,
+    {
+        format!("{}", Dot(self))
+    }
+
+    /// Export `self` as state diagram in the language of [Mermaid](https://mermaid.js.org).
+    ///
+    /// Every state with substates becomes a composite state and every transition is placed in the innermost state containing its source and target.
+    /// Otherwise, the export is labeled like the one of [`to_dot`](`Hsm::to_dot`).
+    ///
+    /// # Examples
+    ///
+    /// Drawing a light:
+    ///
+    /// ```
+    /// use rspl::hsm::Hsm;
+    ///
+    /// let hsm = Hsm::new("off").transition("off", (), "on", [true]);
+    ///
+    /// assert!(hsm.to_mermaid().contains("s0 --> s1 : () / [true]"));
+    /// ```
+    pub fn to_mermaid(&self) -> String
+    where
+        S: fmt::Debug,
+        A: fmt::Debug,
+        B: fmt::Debug,
+    {
+        format!("{}", Mermaid(self))
+    }
+
+    /// Look up the node of a state.
+    /// - `state` is the state.
+    fn node(&self, state: &S) -> Option<&Node<S, B>> {
+        self.nodes.iter().find(|node| node.state == *state)
+    }
+
+    /// Look up the node of a state creating it if it does not exist yet.
+    /// - `state` is the state.
+    fn node_mut(&mut self, state: S) -> &mut Node<S, B> {
+        let index = match self.nodes.iter().position(|node| node.state == state) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(Node {
+                    state,
+                    parent: None,
+                    substates: Vec::new(),
+                    history: None,
+                    entry: Vec::new(),
+                    exit: Vec::new(),
+                });
+                self.nodes.len() - 1
+            }
+        };
+        &mut self.nodes[index]
+    }
+
+    /// Return a state followed by its ancestors from the innermost to the outermost.
+    /// - `state` is the state.
+    fn lineage<'s>(&'s self, state: &'s S) -> Vec<&'s S> {
+        let mut lineage = Vec::from([state]);
+        while let Some(parent) = self.parent(lineage[lineage.len() - 1]) {
+            lineage.push(parent);
+        }
+        lineage
+    }
+
+    /// Determine the innermost state which is neither exited nor entered by a transition or `None` if the transition exits a top-level state.
+    /// - `source` is the state the transition starts in.
+    /// - `target` is the state the transition ends in.
+    fn domain<'s>(&'s self, source: &'s S, target: &'s S) -> Option<&'s S> {
+        if self.lineage(target)[1..].contains(&source) {
+            return Some(source);
+        }
+
+        self.container(source, target)
+    }
+
+    /// Determine the innermost state having two states as substates (of substates ...) or `None` if there is no such state.
+    /// - `source` is the one state.
+    /// - `target` is the other state.
+    fn container<'s>(&'s self, source: &'s S, target: &'s S) -> Option<&'s S> {
+        let targets = self.lineage(target);
+
+        self.lineage(source)
+            .into_iter()
+            .skip(1)
+            .find(|state| targets[1..].contains(state))
+    }
+
+    /// Exit the active states up to a certain state writing their exit outputs and remembering the history.
+    /// - `configuration` is the configuration.
+    /// - `domain` is the state up to which (exclusively) the states are exited or `None` if all states are exited.
+    /// - `outputs` collects the outputs written.
+    fn exit_to(
+        &self,
+        configuration: &mut Configuration<S>,
+        domain: Option<&S>,
+        outputs: &mut Vec<B>,
+    ) where
+        B: Clone,
+    {
+        let leaf = configuration.state.clone();
+        let mut state = leaf.clone();
+
+        while Some(&state) != domain {
+            let Some(node) = self.node(&state) else {
+                break;
+            };
+            outputs.extend(node.exit.iter().cloned());
+
+            let Some(parent) = node.parent.clone() else {
+                break;
+            };
+            match self.node(&parent).and_then(|node| node.history) {
+                Some(History::Shallow) => configuration.remember(parent.clone(), state),
+                Some(History::Deep) => configuration.remember(parent.clone(), leaf.clone()),
+                None => {}
+            }
+            state = parent;
+        }
+    }
+
+    /// Enter a state from a certain state writing the entry outputs and descending to a leaf state.
+    /// - `configuration` is the configuration.
+    /// - `domain` is the state from which (exclusively) the states are entered or `None` if the top-level state is entered too.
+    /// - `target` is the state to be entered.
+    /// - `outputs` collects the outputs written.
+    fn enter(
+        &self,
+        configuration: &mut Configuration<S>,
+        domain: Option<&S>,
+        target: &S,
+        outputs: &mut Vec<B>,
+    ) where
+        B: Clone,
+    {
+        self.descend(domain, target, outputs);
+
+        let mut state = target.clone();
+        while let Some(node) = self.node(&state) {
+            let Some(initial) = node.substates.first() else {
+                break;
+            };
+
+            let next = match (node.history, configuration.remembered(&state)) {
+                (Some(_), Some(remembered)) => remembered.clone(),
+                _ => initial.clone(),
+            };
+
+            self.descend(Some(&state), &next, outputs);
+            state = next;
+        }
+
+        configuration.state = state;
+    }
+
+    /// Write the entry outputs of the states from a certain state down to another state.
+    /// - `from` is the state from which (exclusively) the entry outputs are written or `None` if the top-level state is included.
+    /// - `to` is the state down to which (inclusively) the entry outputs are written.
+    /// - `outputs` collects the outputs written.
+    fn descend(&self, from: Option<&S>, to: &S, outputs: &mut Vec<B>)
+    where
+        B: Clone,
+    {
+        let lineage = self.lineage(to);
+        let entered = lineage
+            .iter()
+            .take_while(|state| Some(**state) != from)
+            .collect::<Vec<_>>();
+
+        for state in entered.into_iter().rev() {
+            if let Some(node) = self.node(state) {
+                outputs.extend(node.entry.iter().cloned());
+            }
+        }
+    }
+}
+
+impl<S, A, B> Hsm<'_, S, A, B>
+where
+    S: PartialEq + Clone,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Describe a state for the exports by its history, entry outputs, exit outputs and internal transitions.
+    /// - `state` is the state.
+    fn description(&self, state: &S) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if let Some(node) = self.node(state) {
+            match node.history {
+                Some(History::Shallow) => lines.push(String::from("(H)")),
+                Some(History::Deep) => lines.push(String::from("(H*)")),
+                None => {}
+            }
+            if !node.entry.is_empty() {
+                lines.push(format!("entry / {:?}", node.entry));
+            }
+            if !node.exit.is_empty() {
+                lines.push(format!("exit / {:?}", node.exit));
+            }
+        }
+
+        for rule in &self.rules {
+            if rule.source == *state && rule.target.is_none() {
+                lines.push(trigger_label(&rule.trigger.label(), &rule.outputs));
+            }
+        }
+
+        lines
+    }
+}
+
+/// [`Dot`] wraps an [`Hsm`] to print it in the DOT-language.
+struct Dot<'h, 'a, S, A, B>(&'h Hsm<'a, S, A, B>);
+
+impl<S, A, B> Dot<'_, '_, S, A, B>
+where
+    S: PartialEq + Clone + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print a state as node or, if it has substates, as cluster.
+    /// - `f` is the formatter.
+    /// - `states` are all states.
+    /// - `state` is the state.
+    /// - `indent` is the indentation.
+    fn state(
+        &self,
+        f: &mut fmt::Formatter,
+        states: &[&S],
+        state: &S,
+        indent: usize,
+    ) -> fmt::Result {
+        let hsm = self.0;
+        let id = state_id(states, state);
+
+        let mut lines = Vec::from([format!("{:?}", state)]);
+        lines.extend(hsm.description(state));
+
+        let substates = hsm.substates(state);
+        let Some(initial) = substates.first() else {
+            return writeln!(
+                f,
+                "{:indent$}s{} [label=\"{}\"];",
+                "",
+                id,
+                escape_dot(&lines)
+            );
+        };
+
+        // The node of a state with substates is its initial pseudostate.
+        writeln!(f, "{:indent$}subgraph cluster_s{} {{", "", id)?;
+        writeln!(f, "{:indent$}    label=\"{}\";", "", escape_dot(&lines))?;
+        writeln!(f, "{:indent$}    s{} [shape=point];", "", id)?;
+        for substate in substates {
+            self.state(f, states, substate, indent + 4)?;
+        }
+        self.edge(f, states, indent + 4, &format!("s{}", id), initial, None)?;
+        writeln!(f, "{:indent$}}}", "")
+    }
+
+    /// Print an edge.
+    /// - `f` is the formatter.
+    /// - `states` are all states.
+    /// - `indent` is the indentation.
+    /// - `source` is the node the edge starts at.
+    /// - `target` is the state the edge ends at.
+    /// - `rule` is the transition the edge represents if any.
+    fn edge(
+        &self,
+        f: &mut fmt::Formatter,
+        states: &[&S],
+        indent: usize,
+        source: &str,
+        target: &S,
+        rule: Option<&Rule<S, A, B>>,
+    ) -> fmt::Result {
+        let hsm = self.0;
+
+        let mut attributes = Vec::new();
+        if let Some(rule) = rule {
+            let label = trigger_label(&rule.trigger.label(), &rule.outputs);
+            attributes.push(format!("label=\"{}\"", escape_dot(&[label])));
+            if !hsm.substates(&rule.source).is_empty() {
+                attributes.push(format!("ltail=cluster_{}", source));
+            }
+        }
+        if !hsm.substates(target).is_empty() {
+            attributes.push(format!("lhead=cluster_s{}", state_id(states, target)));
+        }
+
+        write!(
+            f,
+            "{:indent$}{} -> s{}",
+            "",
+            source,
+            state_id(states, target)
+        )?;
+        if !attributes.is_empty() {
+            write!(f, " [{}]", attributes.join(", "))?;
+        }
+        writeln!(f, ";")
+    }
+}
+
+impl<S, A, B> fmt::Display for Dot<'_, '_, S, A, B>
+where
+    S: PartialEq + Clone + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print the states as nested clusters and nodes and the transitions as edges.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hsm = self.0;
+        let states = hsm.states();
+
+        writeln!(f, "digraph {{")?;
+        writeln!(f, "    compound=true;")?;
+        writeln!(f, "    initial [shape=point];")?;
+        for state in states.iter().filter(|state| hsm.parent(state).is_none()) {
+            self.state(f, &states, state, 4)?;
+        }
+        self.edge(f, &states, 4, "initial", &hsm.initial, None)?;
+        for rule in &hsm.rules {
+            if let Some(target) = &rule.target {
+                let source = format!("s{}", state_id(&states, &rule.source));
+                self.edge(f, &states, 4, &source, target, Some(rule))?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// [`Mermaid`] wraps an [`Hsm`] to print it in the language of Mermaid.
+struct Mermaid<'h, 'a, S, A, B>(&'h Hsm<'a, S, A, B>);
+
+impl<S, A, B> Mermaid<'_, '_, S, A, B>
+where
+    S: PartialEq + Clone + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print a state with its description and, if it has substates, as composite state.
+    /// - `f` is the formatter.
+    /// - `states` are all states.
+    /// - `state` is the state.
+    /// - `indent` is the indentation.
+    fn state(
+        &self,
+        f: &mut fmt::Formatter,
+        states: &[&S],
+        state: &S,
+        indent: usize,
+    ) -> fmt::Result {
+        let hsm = self.0;
+        let id = state_id(states, state);
+
+        let name = escape_mermaid(&format!("{:?}", state));
+        writeln!(f, "{:indent$}state \"{}\" as s{}", "", name, id)?;
+        for line in hsm.description(state) {
+            writeln!(f, "{:indent$}s{} : {}", "", id, escape_mermaid(&line))?;
+        }
+
+        let substates = hsm.substates(state);
+        let Some(initial) = substates.first() else {
+            return Ok(());
+        };
+
+        writeln!(f, "{:indent$}state s{} {{", "", id)?;
+        for substate in substates {
+            self.state(f, states, substate, indent + 4)?;
+        }
+        writeln!(
+            f,
+            "{:indent$}    [*] --> s{}",
+            "",
+            state_id(states, initial)
+        )?;
+        self.transitions(f, states, Some(state), indent + 4)?;
+        writeln!(f, "{:indent$}}}", "")
+    }
+
+    /// Print the transitions whose innermost state containing their source and target is a certain state.
+    /// - `f` is the formatter.
+    /// - `states` are all states.
+    /// - `container` is the state or `None` for the transitions between top-level states.
+    /// - `indent` is the indentation.
+    fn transitions(
+        &self,
+        f: &mut fmt::Formatter,
+        states: &[&S],
+        container: Option<&S>,
+        indent: usize,
+    ) -> fmt::Result {
+        let hsm = self.0;
+
+        for rule in &hsm.rules {
+            let Some(target) = &rule.target else {
+                continue;
+            };
+            if hsm.container(&rule.source, target) == container {
+                writeln!(
+                    f,
+                    "{:indent$}s{} --> s{} : {}",
+                    "",
+                    state_id(states, &rule.source),
+                    state_id(states, target),
+                    escape_mermaid(&trigger_label(&rule.trigger.label(), &rule.outputs))
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S, A, B> fmt::Display for Mermaid<'_, '_, S, A, B>
+where
+    S: PartialEq + Clone + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print the states with their substates and the transitions between top-level states.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hsm = self.0;
+        let states = hsm.states();
+
+        writeln!(f, "stateDiagram-v2")?;
+        for state in states.iter().filter(|state| hsm.parent(state).is_none()) {
+            self.state(f, &states, state, 4)?;
+        }
+        writeln!(f, "    [*] --> s{}", state_id(&states, &hsm.initial))?;
+        self.transitions(f, &states, None, 4)
+    }
+}
+
+/// Construct the stream processor behaving like a hierarchical state machine in a certain configuration.
+/// - `hsm` is the machine.
+/// - `configuration` is the current configuration.
+fn lower<'a, S, A, B>(
+    hsm: Rc<Hsm<'a, S, A, B>>,
+    configuration: Configuration<S>,
+) -> StreamProcessor<'a, A, B>
+where
+    S: PartialEq + Clone + 'a,
+    A: 'a,
+    B: Clone + 'a,
+{
+    StreamProcessor::Get(box_fn(move |a: A| {
+        let mut configuration = configuration;
+        match hsm.step(&mut configuration, &a) {
+            Some(outputs) => puts(outputs, move || lower(hsm, configuration)),
+            None => lower(hsm, configuration),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runners::PushRunner;
+
+    use alloc::vec;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Color {
+        Red,
+        Yellow,
+        Green,
+        Black,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Event {
+        Push,
+        Timeout,
+        Exit,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Capability {
+        SetVehicleLights(Color),
+        SetPedestrianLights(Color),
+        EmitTimeoutAfter(u64),
+        UnexpectedTimeout,
+        CallForHelp,
+        Break,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Pelican {
+        On,
+        Operational,
+        Vehicles,
+        VehiclesGreenGuard,
+        VehiclesGreen,
+        VehiclesGreenPushed,
+        VehiclesGreenTimedout,
+        VehiclesYellow,
+        Pedestrians,
+        PedestriansGreenGuard,
+        PedestriansGreen,
+        Error,
+        Off,
+    }
+
+    // This is the machine of the pelican example expressed as data.
+    fn pelican<'a>() -> Hsm<'a, Pelican, Event, Capability> {
+        use Capability::*;
+        use Pelican::*;
+
+        let is = |event: Event| move |e: &Event| *e == event;
+
+        Hsm::new(On)
+            .substate(On, Operational)
+            .substate(Operational, Vehicles)
+            .substate(Operational, Pedestrians)
+            .substate(Vehicles, VehiclesGreenGuard)
+            .substate(Vehicles, VehiclesGreen)
+            .substate(Vehicles, VehiclesGreenPushed)
+            .substate(Vehicles, VehiclesGreenTimedout)
+            .substate(Vehicles, VehiclesYellow)
+            .substate(Pedestrians, PedestriansGreenGuard)
+            .substate(Pedestrians, PedestriansGreen)
+            .entry(
+                On,
+                [
+                    SetPedestrianLights(Color::Red),
+                    SetVehicleLights(Color::Red),
+                ],
+            )
+            .entry(
+                Vehicles,
+                [SetPedestrianLights(Color::Red), EmitTimeoutAfter(2000)],
+            )
+            .entry(
+                VehiclesGreen,
+                [SetVehicleLights(Color::Green), EmitTimeoutAfter(10000)],
+            )
+            .entry(
+                VehiclesYellow,
+                [SetVehicleLights(Color::Yellow), EmitTimeoutAfter(1000)],
+            )
+            .entry(
+                Pedestrians,
+                [SetVehicleLights(Color::Red), EmitTimeoutAfter(2000)],
+            )
+            .entry(
+                PedestriansGreen,
+                [SetPedestrianLights(Color::Green), EmitTimeoutAfter(10000)],
+            )
+            .entry(
+                Error,
+                [
+                    SetPedestrianLights(Color::Red),
+                    SetVehicleLights(Color::Red),
+                    CallForHelp,
+                ],
+            )
+            .entry(
+                Off,
+                [
+                    SetPedestrianLights(Color::Black),
+                    SetVehicleLights(Color::Black),
+                    Break,
+                ],
+            )
+            .transition(Operational, Event::Exit, Off, [])
+            .transition(VehiclesGreenGuard, Event::Timeout, VehiclesGreen, [])
+            .transition(VehiclesGreen, Event::Push, VehiclesGreenPushed, [])
+            .transition(VehiclesGreen, Event::Timeout, VehiclesGreenTimedout, [])
+            .transition(VehiclesGreenPushed, Event::Timeout, VehiclesYellow, [])
+            .transition(VehiclesGreenTimedout, Event::Push, VehiclesYellow, [])
+            .transition_if(
+                VehiclesGreenTimedout,
+                is(Event::Timeout),
+                Error,
+                [UnexpectedTimeout],
+            )
+            .transition(VehiclesYellow, Event::Timeout, Pedestrians, [])
+            .transition(PedestriansGreenGuard, Event::Timeout, PedestriansGreen, [])
+            .transition(PedestriansGreen, Event::Timeout, Vehicles, [])
+            .internal(Error, |_| true, [CallForHelp])
+            .internal(Off, |_| true, [Break])
+    }
+
+    #[test]
+    fn test_pelican() {
+        use Capability::*;
+
+        let hsm = pelican();
+
+        let (outputs, configuration) = hsm.run([
+            Event::Push,
+            Event::Timeout,
+            Event::Push,
+            Event::Timeout,
+            Event::Timeout,
+            Event::Timeout,
+            Event::Timeout,
+        ]);
+
+        assert_eq!(
+            outputs,
+            vec![
+                SetPedestrianLights(Color::Red),
+                SetVehicleLights(Color::Red),
+                SetPedestrianLights(Color::Red),
+                EmitTimeoutAfter(2000),
+                SetVehicleLights(Color::Green),
+                EmitTimeoutAfter(10000),
+                SetVehicleLights(Color::Yellow),
+                EmitTimeoutAfter(1000),
+                SetVehicleLights(Color::Red),
+                EmitTimeoutAfter(2000),
+                SetPedestrianLights(Color::Green),
+                EmitTimeoutAfter(10000),
+                SetPedestrianLights(Color::Red),
+                EmitTimeoutAfter(2000),
+            ]
+        );
+        assert_eq!(configuration.state(), &Pelican::VehiclesGreenGuard);
+        assert!(hsm.is_in(&configuration, &Pelican::Operational));
+
+        let (outputs, configuration) = hsm.run([Event::Timeout, Event::Timeout, Event::Timeout]);
+
+        assert_eq!(
+            outputs[outputs.len() - 4..],
+            [
+                UnexpectedTimeout,
+                SetPedestrianLights(Color::Red),
+                SetVehicleLights(Color::Red),
+                CallForHelp,
+            ]
+        );
+        assert_eq!(configuration.state(), &Pelican::Error);
+
+        let (outputs, configuration) = hsm.run([Event::Timeout, Event::Exit, Event::Push]);
+
+        assert_eq!(
+            outputs[outputs.len() - 4..],
+            [
+                SetPedestrianLights(Color::Black),
+                SetVehicleLights(Color::Black),
+                Break,
+                Break,
+            ]
+        );
+        assert!(!hsm.is_in(&configuration, &Pelican::On));
+    }
+
+    fn nested<'a>(history: Option<History>) -> Hsm<'a, &'static str, char, &'static str> {
+        let hsm = Hsm::new("top")
+            .substate("top", "outer")
+            .substate("top", "other")
+            .substate("outer", "a")
+            .substate("outer", "b")
+            .substate("b", "b1")
+            .substate("b", "b2")
+            .entry("outer", ["+outer"])
+            .exit("outer", ["-outer"])
+            .entry("b", ["+b"])
+            .exit("b", ["-b"])
+            .entry("b2", ["+b2"])
+            .exit("b2", ["-b2"])
+            .entry("other", ["+other"])
+            .exit("other", ["-other"])
+            .transition("a", 'x', "b2", ["x"])
+            .transition("outer", 'o', "other", ["o"])
+            .transition("other", 'i', "outer", ["i"])
+            .transition("b2", 's', "b2", ["s"])
+            .transition("outer", 'u', "b", ["u"]);
+
+        match history {
+            Some(history) => hsm.history("outer", history),
+            None => hsm,
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let hsm = nested(None);
+        let (outputs, mut configuration) = hsm.start();
+        assert_eq!(outputs, vec!["+outer"]);
+        assert_eq!(configuration.state(), &"a");
+
+        assert_eq!(
+            hsm.step(&mut configuration, &'x'),
+            Some(vec!["x", "+b", "+b2"])
+        );
+        assert_eq!(
+            hsm.step(&mut configuration, &'s'),
+            Some(vec!["-b2", "s", "+b2"])
+        );
+        assert_eq!(
+            hsm.step(&mut configuration, &'u'),
+            Some(vec!["-b2", "-b", "u", "+b"])
+        );
+        assert_eq!(configuration.state(), &"b1");
+        assert_eq!(
+            hsm.step(&mut configuration, &'o'),
+            Some(vec!["-b", "-outer", "o", "+other"])
+        );
+        assert_eq!(hsm.step(&mut configuration, &'x'), None);
+        assert_eq!(
+            hsm.step(&mut configuration, &'i'),
+            Some(vec!["-other", "i", "+outer"])
+        );
+        assert_eq!(configuration.state(), &"a");
+    }
+
+    #[test]
+    #[should_panic(expected = "states must not be substates of their substates")]
+    fn test_substate_cycle() {
+        let _: Hsm<&str, (), ()> = Hsm::new("a").substate("a", "b").substate("b", "a");
+    }
+
+    #[test]
+    #[should_panic(expected = "the transition added last must be guarded")]
+    fn test_guard_label() {
+        let _: Hsm<&str, usize, ()> = Hsm::new("off")
+            .transition_if("off", |n: &usize| *n > 0, "on", [])
+            .guard_label("n > 0")
+            .transition("on", 0, "off", [])
+            .guard_label("n == 0");
+    }
+
+    #[test]
+    fn test_history() {
+        let inputs = ['x', 'o', 'i'];
+
+        let (_, configuration) = nested(None).run(inputs);
+        assert_eq!(configuration.state(), &"a");
+
+        let (_, configuration) = nested(Some(History::Shallow)).run(inputs);
+        assert_eq!(configuration.state(), &"b1");
+
+        let (outputs, configuration) = nested(Some(History::Deep)).run(inputs);
+        assert_eq!(configuration.state(), &"b2");
+        assert_eq!(outputs[outputs.len() - 3..], ["+outer", "+b", "+b2"]);
+    }
+
+    #[test]
+    fn test_into_stream_processor() {
+        const INPUTS: [Event; 6] = [
+            Event::Timeout,
+            Event::Push,
+            Event::Timeout,
+            Event::Timeout,
+            Event::Exit,
+            Event::Push,
+        ];
+
+        let mut runner = PushRunner::new(pelican().into_stream_processor());
+
+        let mut outputs = runner.pending().collect::<Vec<_>>();
+        for event in INPUTS {
+            outputs.extend(runner.feed(event));
+        }
+
+        assert_eq!(outputs, pelican().run(INPUTS).0);
+    }
+
+    fn drawing<'a>() -> Hsm<'a, &'static str, usize, char> {
+        Hsm::new("on")
+            .substate("on", "idle")
+            .substate("on", "busy")
+            .history("on", History::Shallow)
+            .entry("busy", ['b'])
+            .exit("on", ['x'])
+            .transition("idle", 1, "busy", [])
+            .transition_if("busy", |n: &usize| *n > 1, "idle", ['i'])
+            .guard_label("n > 1")
+            .internal("busy", |_| true, ['?'])
+            .transition("on", 0, "off", [])
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            drawing().to_dot(),
+            r#"digraph {
+    compound=true;
+    initial [shape=point];
+    subgraph cluster_s0 {
+        label="\"on\"\n(H)\nexit / ['x']";
+        s0 [shape=point];
+        s1 [label="\"idle\""];
+        s2 [label="\"busy\"\nentry / ['b']\n[guard] / ['?']"];
+        s0 -> s1;
+    }
+    s3 [label="\"off\""];
+    initial -> s0 [lhead=cluster_s0];
+    s1 -> s2 [label="1"];
+    s2 -> s1 [label="[n > 1] / ['i']"];
+    s0 -> s3 [label="0", ltail=cluster_s0];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        assert_eq!(
+            drawing().to_mermaid(),
+            r##"stateDiagram-v2
+    state "#quot;on#quot;" as s0
+    s0 : (H)
+    s0 : exit / ['x']
+    state s0 {
+        state "#quot;idle#quot;" as s1
+        state "#quot;busy#quot;" as s2
+        s2 : entry / ['b']
+        s2 : [guard] / ['?']
+        [*] --> s1
+        s1 --> s2 : 1
+        s2 --> s1 : [n > 1] / ['i']
+    }
+    state "#quot;off#quot;" as s3
+    [*] --> s0
+    s0 --> s3 : 0
+"##
+        );
+    }
+}
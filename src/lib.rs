@@ -40,6 +40,9 @@
 //! ...                                      ...
 //! </pre>
 //!
+//! Additionally, there is a third kind of stream processor ('done') which neither reads nor writes but stops processing with a result.
+//! It allows to run stream processors one after another like the phases of a protocol (see [`bind`](`combinators::bind`)).
+//!
 //! Remarkably, the language constructs are somewhat dual and loosely correspond to (dual) programming paradigms:
 //! - The `Get`-construct relates to event-driven programming as it reacts to input (events) eagerly.
 //! - The `Put`-construct relates to demand-driven[^1] programming as it generates output (demands) iteratively by need.
@@ -48,15 +51,36 @@
 //!
 //! ## Usage
 //!
-//! To program a rspl-[`StreamProcessor`] you just have to compose the constructors [`StreamProcessor::Get`]/[`get`](`StreamProcessor::get`) and [`StreamProcessor::Put`]/[`put`](`StreamProcessor::put`) in the right way.
+//! To program a rspl-[`StreamProcessor`] you just have to compose the constructors [`StreamProcessor::Get`]/[`get`](`StreamProcessor::get`), [`StreamProcessor::Put`]/[`put`](`StreamProcessor::put`) and [`StreamProcessor::Done`] in the right way.
 //! For a somewhat more high-level programming experience you might wish to look at the [`combinators`]-module.
+//! And if performance matters, chains of maps and filters can be built with the [`fusion`]-module instead.
+//! Moreover, with the `inline-closures`-feature enabled, small closures are stored inline instead of on the heap (see the [`closures`]-module).
+//! If there is no allocator at all, the [`heapless`]-module provides a closure-converted variant of the language together with its most important combinators.
+//! It is the only part of the language which remains when both the `std`- and the `alloc`-feature are disabled.
 //! The program can then be evaluated with the [`eval`](`StreamProcessor::eval`)-method on some kind of input stream.
 //! The 'kind' of input stream is either your own implementation of the [`Stream`]-interface or one
 //! from the submodules of the [`streams`]-module.
 //! Either way, as result, evaluation produces an [`InfiniteList`] (lazily).
+//! Alternatively, stream processors can be evaluated with the [`eval_finite`](`StreamProcessor::eval_finite`)-method on [`FiniteStream`]s which then produces a [`FiniteList`] (lazily).
+//! And for convenience, the [`eval_iter`](`StreamProcessor::eval_iter`)-method evaluates stream processors on anything which can be turned into an iterator producing an iterator.
+//! If input is rather pushed to the program than pulled from a stream (like in the callbacks of a foreign event loop), the program can instead be run with a [`PushRunner`](`runners::PushRunner`) from the [`runners`]-module.
+//! Similarly, if input shall be polled without blocking, the program can be run with a [`PollRunner`](`runners::PollRunner`).
+//! And with the `async`-feature enabled, the program can be evaluated asynchronously on an asynchronous input stream with the `eval_async`-method.
 //! To observe streams - and i.p. infinite lists - you can destruct them with the [`head`](`Stream::head`)- and [`tail`](`Stream::tail`)-methods of the stream interface.
 //! Moreover, there are various functions helping with the destruction and construction of streams.
 //!
+//! ## Features
+//!
+//! rspl has the following features:
+//! - `std` (enabled by default) provides the streams based on channels and implies `alloc`.
+//! - `alloc` provides [`StreamProcessor`] together with everything built upon it like the [`combinators`]-module and the list streams.
+//!   Note that, other than with earlier versions, disabling the default features disables all of this as well.
+//!   So, to use rspl without `std` but with an allocator, depend on it with `default-features = false` and `features = ["alloc"]`.
+//! - `async` provides the `eval_async`-method and implies `alloc`.
+//! - `inline-closures` stores small closures inline (see the [`closures`]-module) and implies `alloc`.
+//!
+//! Without any feature only the [`heapless`]-module and the [`streams`]-interfaces remain.
+//!
 //! # Examples
 //!
 //! As alluded to in the [Design](#design)-section, rspl supports orthodox 'combinator-driven' stream processing as it is known from list processing with combinators like [`compose`](`combinators::compose`), [`filter`](`combinators::filter`) and [`map`](`combinators::map`).
@@ -125,6 +149,10 @@
 //!   ```
 //!
 //!   A slightly more concrete example using that pattern is available as [integration test](https://github.com/shtsoft/rspl/blob/master/tests/events.rs).
+//!   Alternatively, state machines can be described as data with the [`machines`]-module and lowered to stream processors afterwards.
+//!   Or they can be written down with the [`machine`]-macro which expands to the type-state encoding above without its boilerplate.
+//!   For hierarchical state machines with entry and exit outputs, guards and history there is the [`hsm`]-module.
+//!   However they are written down, machines reading inputs from a finite alphabet can be checked for bad outputs with the [`analysis`]-module.
 //!   And a full-blown concrete example of a pelican crossing can be found [here (as .md file)](https://github.com/shtsoft/rspl/blob/master/examples/pelican.md) and [here (as .rs file)](https://github.com/shtsoft/rspl/blob/master/examples/pelican.rs).
 //!   Notably, it uses rspl to encode effectful hierarchical state machines with a capability-passing inspired effect-handling mechanism.
 //! - demand-driven programming with generators as suggested [here](https://www.cse.chalmers.se/~rjmh/Papers/whyfp.pdf).
@@ -170,42 +198,81 @@
 //! [^1]: Look at [Codata in Action](https://www.microsoft.com/en-us/research/uploads/prod/2020/01/CoDataInAction.pdf) for some more explanation on that term.
 
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "inline-closures"), forbid(unsafe_code))]
+#![cfg_attr(feature = "inline-closures", deny(unsafe_code))]
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
+pub mod analysis;
+
+#[cfg(feature = "alloc")]
+pub mod closures;
+
+#[cfg(feature = "alloc")]
 pub mod combinators;
 
+#[cfg(feature = "alloc")]
+pub mod fusion;
+
+pub mod heapless;
+
+#[cfg(feature = "alloc")]
+pub mod hsm;
+
+#[cfg(feature = "alloc")]
+pub mod machines;
+
+#[cfg(feature = "alloc")]
+pub mod runners;
+
 pub mod streams;
 
+#[cfg(feature = "alloc")]
+use streams::finite_lists::FiniteList;
+#[cfg(feature = "alloc")]
 use streams::infinite_lists::InfiniteList;
-use streams::Stream;
+#[cfg(feature = "alloc")]
+use streams::iterator_streams::IteratorStream;
+#[cfg(feature = "alloc")]
+use streams::{FiniteStream, Stream};
 
-use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use closures::{box_fn, box_lazy, Apply, BoxedFn, BoxedLazy, Force};
 
-/// [`Lazy<T>`] types thunks of type `T`.
-type Lazy<'a, T> = dyn FnOnce() -> T + 'a;
+#[cfg(feature = "alloc")]
+use core::convert::Infallible;
 
-/// [`StreamProcessor<A, B>`] defines (the syntax of) a language describing the domain of stream processors, that is, terms which can be interpreted to turn streams of type `A` into streams of type `B`.
-pub enum StreamProcessor<'a, A: 'a, B> {
+#[cfg(feature = "alloc")]
+/// [`StreamProcessor<A, B, R>`] defines (the syntax of) a language describing the domain of stream processors, that is, terms which can be interpreted to turn streams of type `A` into streams of type `B` possibly terminating with a result of type `R`.
+///
+/// By default `R` is the empty type [`Infallible`] which means that the stream processor never terminates.
+pub enum StreamProcessor<'a, A: 'a, B, R = Infallible> {
     /// This stream processor first reads the `A` from the head of the input stream and subsequently applies its function argument to that element yielding a stream processor.
     /// The resulting stream processor is then used to process the input stream further depending on its shape: if it is a
     /// - [`Get`](`StreamProcessor::Get`), it is applied to the tail of the input stream.
     /// - [`Put`](`StreamProcessor::Put`), it is applied to the whole input stream.
-    Get(Box<dyn FnOnce(A) -> StreamProcessor<'a, A, B> + 'a>),
+    /// - [`Done`](`StreamProcessor::Done`), it stops processing.
+    Get(BoxedFn<'a, A, StreamProcessor<'a, A, B, R>>),
     /// This stream processor writes the `B` from its first argument to the output list.
     /// Then, to construct the rest of the output list, it uses its second argument to process the input stream depending on its shape: if it is a
     /// - [`Get`](`StreamProcessor::Get`), it is applied to the tail of the input stream.
     /// - [`Put`](`StreamProcessor::Put`), it is applied to the whole input stream.
-    Put(B, Box<Lazy<'a, StreamProcessor<'a, A, B>>>),
+    /// - [`Done`](`StreamProcessor::Done`), it stops processing.
+    Put(B, BoxedLazy<'a, StreamProcessor<'a, A, B, R>>),
+    /// This stream processor neither reads nor writes anything but stops processing with the `R` from its argument as result.
+    Done(R),
 }
 
-impl<'a, A, B> StreamProcessor<'a, A, B> {
+#[cfg(feature = "alloc")]
+impl<'a, A, B, R> StreamProcessor<'a, A, B, R> {
     /// The same as [`StreamProcessor::Get`] but with boxing of `f` hidden to make the resulting code less verbose.
     #[inline]
     pub fn get<F>(f: F) -> Self
     where
         F: FnOnce(A) -> Self + 'a,
     {
-        StreamProcessor::Get(Box::new(f))
+        StreamProcessor::Get(box_fn(f))
     }
 
     /// The same as [`StreamProcessor::Put`] but with boxing of `lazy_sp` hidden to make the resulting code less verbose.
@@ -215,15 +282,18 @@ impl<'a, A, B> StreamProcessor<'a, A, B> {
         B: 'a,
         T: FnOnce() -> Self + 'a,
     {
-        StreamProcessor::Put(b, Box::new(lazy_sp))
+        StreamProcessor::Put(b, box_lazy(lazy_sp))
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, A, B> StreamProcessor<'a, A, B> {
     /// Evaluate `self` on an input stream essentially implementing a semantic of [`StreamProcessor<A, B>`].
     /// - `stream` is the input stream.
     ///
     /// Note that the function can block the current thread if the respective implementation of [`Stream::tail`] can.
+    /// Moreover, note that only non-terminating stream processors can be evaluated this way as the output is an infinite list.
+    /// Terminating stream processors have to be sequenced into a non-terminating one with [`bind`](`combinators::bind`) first.
     ///
     /// # Panics
     ///
@@ -261,35 +331,208 @@ impl<'a, A, B> StreamProcessor<'a, A, B> {
         loop {
             match self {
                 StreamProcessor::Get(f) => {
-                    self = f(stream.head().clone());
+                    self = f.apply(stream.head().clone());
                     while let StreamProcessor::Get(f) = self {
                         stream = stream.tail();
-                        self = f(stream.head().clone());
+                        self = f.apply(stream.head().clone());
                     }
                     continue;
                 }
                 StreamProcessor::Put(b, lazy_sp) => {
-                    return InfiniteList::Cons(
-                        b,
-                        Box::new(|| {
-                            let sp = lazy_sp();
-                            if let StreamProcessor::Get(_) = sp {
+                    return InfiniteList::cons(b, || {
+                        let sp = lazy_sp.force();
+                        if let StreamProcessor::Get(_) = sp {
+                            stream = stream.tail();
+                        }
+                        Self::eval(sp, stream)
+                    })
+                }
+                StreamProcessor::Done(never) => match never {},
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, A, B, R: 'a> StreamProcessor<'a, A, B, R> {
+    /// Evaluate `self` on a finite input stream in the same way as [`eval`](`StreamProcessor::eval`) but producing a finite output.
+    /// - `stream` is the finite input stream.
+    ///
+    /// The output ends as soon as `self` either wants to read from an ended input stream or terminates.
+    /// To let the stream processor react to the end of the input stream (for example, to flush buffered state) evaluate it on the [`terminated`](`FiniteStream::terminated`) input stream instead.
+    /// Moreover, the input stream is only advanced after its head has been read, so no item is skipped if `self` writes before it reads.
+    ///
+    /// Note that the function can block the current thread if the respective implementation of [`FiniteStream::tail`] can.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the stream processor contains Rust-terms which can panic.
+    /// - the respective implementation of [`FiniteStream::head`] or [`FiniteStream::tail`] can panic.
+    ///
+    /// # Examples
+    ///
+    /// Summing up a finite stream of numbers:
+    ///
+    /// ```
+    /// use rspl::streams::finite_lists::FiniteList;
+    /// use rspl::streams::FiniteStream;
+    /// use rspl::StreamProcessor;
+    ///
+    /// fn sum<'a>(acc: usize) -> StreamProcessor<'a, Option<usize>, usize> {
+    ///     StreamProcessor::get(move |n: Option<usize>| match n {
+    ///         Some(n) => sum(acc + n),
+    ///         None => StreamProcessor::put(acc, || sum(0)),
+    ///     })
+    /// }
+    ///
+    /// let numbers = FiniteList::cons(1, || FiniteList::cons(2, || FiniteList::Nil));
+    ///
+    /// let result = sum(0).eval_finite(numbers.terminated());
+    ///
+    /// assert_eq!(result.head(), Some(&3));
+    /// assert_eq!(result.tail().head(), None);
+    /// ```
+    pub fn eval_finite<S: FiniteStream<A> + 'a>(self, stream: S) -> FiniteList<'a, B>
+    where
+        A: Clone,
+    {
+        self.eval_finite_from(stream, false)
+    }
+
+    /// Evaluate `self` on a finite input stream as [`eval_finite`](`StreamProcessor::eval_finite`) does.
+    /// - `stream` is the finite input stream.
+    /// - `consumed` tells whether the head of `stream` has been read already.
+    fn eval_finite_from<S: FiniteStream<A> + 'a>(
+        mut self,
+        mut stream: S,
+        mut consumed: bool,
+    ) -> FiniteList<'a, B>
+    where
+        A: Clone,
+    {
+        // This implementation follows the one of `eval` with the obvious adaptions to the end of
+        // the input stream. Other than there, the tail is only taken if the head has been read
+        // already. So, a stream processor which writes before it reads sees the whole input.
+        loop {
+            match self {
+                StreamProcessor::Get(f) => {
+                    match stream.head() {
+                        Some(a) => self = f.apply(a.clone()),
+                        None => return FiniteList::Nil,
+                    }
+                    while let StreamProcessor::Get(f) = self {
+                        stream = stream.tail();
+                        match stream.head() {
+                            Some(a) => self = f.apply(a.clone()),
+                            None => return FiniteList::Nil,
+                        }
+                    }
+                    consumed = true;
+                    continue;
+                }
+                StreamProcessor::Put(b, lazy_sp) => {
+                    return FiniteList::cons(b, move || {
+                        let sp = lazy_sp.force();
+                        if let StreamProcessor::Get(_) = sp {
+                            if consumed {
                                 stream = stream.tail();
+                                consumed = false;
                             }
-                            Self::eval(sp, stream)
-                        }),
-                    )
+                        }
+                        Self::eval_finite_from(sp, stream, consumed)
+                    })
                 }
+                StreamProcessor::Done(_) => return FiniteList::Nil,
             }
         }
     }
 }
 
+#[cfg(feature = "alloc")]
+impl<'a, A, B, R: 'a> StreamProcessor<'a, A, B, R> {
+    /// Evaluate `self` on the items of an iterator yielding an iterator over the output.
+    /// - `xs` is what is turned into the input iterator.
+    ///
+    /// This is a shorthand for evaluating `self` with [`eval_finite`](`StreamProcessor::eval_finite`) on an [`IteratorStream`] and iterating over the resulting [`FiniteList`].
+    /// In particular, the output ends as soon as `self` either wants to read from an exhausted input iterator or terminates.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the stream processor contains Rust-terms which can panic.
+    /// - the input iterator can panic.
+    ///
+    /// # Examples
+    ///
+    /// Negating a vector of bools:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    ///
+    /// let negations = negate.eval_iter(vec![true, false]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(negations, vec![false, true]);
+    /// ```
+    pub fn eval_iter<T>(self, xs: T) -> streams::finite_lists::IntoIter<'a, B>
+    where
+        A: Clone,
+        T: IntoIterator<Item = A>,
+        T::IntoIter: 'a,
+    {
+        self.eval_finite(IteratorStream::new(xs)).into_iter()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, A, B, R> StreamProcessor<'a, A, B, R> {
+    /// Evaluate `self` on an asynchronous input stream yielding an asynchronous output stream.
+    /// - `stream` is the asynchronous input stream.
+    ///
+    /// As with [`eval`](`StreamProcessor::eval`) input is taken as late as possible, that is, only if the next step is 'getting'.
+    /// So, writing is never held back by waiting for input.
+    /// The output ends as soon as `self` either wants to read from an ended input stream or terminates.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the stream processor contains Rust-terms which can panic.
+    /// - the respective implementation of [`poll_next`](`futures_core::Stream::poll_next`) can panic.
+    ///
+    /// # Examples
+    ///
+    /// Negating an asynchronous stream of bools:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::StreamProcessor;
+    ///
+    /// use futures::executor::block_on;
+    /// use futures::stream::{iter, StreamExt};
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    /// let trues = iter([true, true]);
+    ///
+    /// let falses = block_on(negate.eval_async(trues).collect::<Vec<_>>());
+    ///
+    /// assert_eq!(falses, vec![false, false]);
+    /// ```
+    pub const fn eval_async<S>(self, stream: S) -> runners::AsyncRunner<'a, A, B, S, R>
+    where
+        S: futures_core::Stream<Item = A> + Unpin,
+    {
+        runners::AsyncRunner::new(self, stream)
+    }
+}
+
 #[cfg(feature = "std")]
 #[cfg(test)]
 mod tests {
     use super::*;
-    use combinators::map;
+    use combinators::{filter, map};
     use streams::overeager_receivers::OvereagerReceiver;
 
     use crate::assert_head_eq;
@@ -302,18 +545,14 @@ mod tests {
 
     #[test]
     fn test_get() {
-        assert!(matches!(
-            StreamProcessor::get(|_: ()| { map(id) }),
-            StreamProcessor::Get(_)
-        ));
+        let sp: StreamProcessor<_, _> = StreamProcessor::get(|_: ()| map(id));
+        assert!(matches!(sp, StreamProcessor::Get(_)));
     }
 
     #[test]
     fn test_put() {
-        assert!(matches!(
-            StreamProcessor::put((), || map(id)),
-            StreamProcessor::Put(_, _)
-        ));
+        let sp: StreamProcessor<_, _> = StreamProcessor::put((), || map(id));
+        assert!(matches!(sp, StreamProcessor::Put(_, _)));
     }
 
     #[test]
@@ -340,6 +579,70 @@ mod tests {
         assert_tail_starts_with!(result, [2, 1]);
     }
 
+    #[test]
+    fn test_eval_finite() {
+        let sp = StreamProcessor::get(|n: usize| {
+            StreamProcessor::put(n, || {
+                StreamProcessor::get(|n: usize| {
+                    StreamProcessor::put(n, || StreamProcessor::Done(()))
+                })
+            })
+        });
+
+        let stream = FiniteList::cons(0, || FiniteList::cons(1, || FiniteList::Nil));
+
+        let result = sp.eval_finite(stream);
+        assert_eq!(result.head(), Some(&0));
+
+        let result = result.tail();
+        assert_eq!(result.head(), Some(&1));
+        assert_eq!(result.tail().head(), None);
+    }
+
+    #[test]
+    fn test_eval_finite_put() {
+        let stream = FiniteList::cons(1, || FiniteList::cons(2, || FiniteList::Nil));
+
+        let sp: StreamProcessor<_, _> = StreamProcessor::put(Some(0), || map(id));
+        let result = sp.eval_finite(stream.terminated());
+        assert_eq!(result.head(), Some(&Some(0)));
+
+        let result = result.tail();
+        assert_eq!(result.head(), Some(&Some(1)));
+
+        let result = result.tail();
+        assert_eq!(result.head(), Some(&Some(2)));
+
+        let result = result.tail();
+        assert_eq!(result.head(), Some(&None));
+        assert_eq!(result.tail().head(), None);
+    }
+
+    #[test]
+    fn test_eval_finite_end() {
+        let stream = FiniteList::cons(true, || FiniteList::Nil);
+
+        let sp: StreamProcessor<_, _> = map(|b: bool| !b);
+        let result = sp.eval_finite(stream);
+        assert_eq!(result.head(), Some(&false));
+        assert_eq!(result.tail().head(), None);
+    }
+
+    #[test]
+    fn test_eval_iter() {
+        let is_greater_zero = |n: &usize| *n > 0;
+
+        let sp: StreamProcessor<_, _> = filter(is_greater_zero);
+        let result = sp.eval_iter(vec![0, 1, 0, 2]).take(5).collect::<Vec<_>>();
+
+        assert_eq!(result, vec![1, 2]);
+
+        let sp: StreamProcessor<_, _> = StreamProcessor::put(0, || map(id));
+        let result = sp.eval_iter(vec![1, 2, 3]).collect::<Vec<_>>();
+
+        assert_eq!(result, vec![0, 1, 2, 3]);
+    }
+
     #[test]
     #[should_panic]
     fn test_eval_panic() {
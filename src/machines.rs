@@ -0,0 +1,923 @@
+//! This module provides state machines which are described as data rather than as nested functions.
+//!
+//! A [`Machine`] consists of an initial state, the outputs written on entering states and a transition table.
+//! As it is just data, it can be inspected, printed and tested before it is lowered to an ordinary [`StreamProcessor`] with [`into_stream_processor`](`Machine::into_stream_processor`).
+//! In particular, it can be drawn by exporting it to [Graphviz](https://graphviz.org) or [Mermaid](https://mermaid.js.org).
+//! For machines whose transitions are better described by a match function than by a table see [`mealy`](`crate::combinators::mealy`) and [`moore`](`crate::combinators::moore`).
+
+use super::closures::{box_fn, box_lazy};
+use super::StreamProcessor;
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::{self, Vec};
+
+use core::fmt;
+
+/// [`Transition<S, A, B>`] describes a row of the transition table of a [`Machine<S, A, B>`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition<S, A, B> {
+    /// state the transition starts in
+    pub source: S,
+    /// input triggering the transition
+    pub input: A,
+    /// state the transition ends in
+    pub target: S,
+    /// outputs written when taking the transition (before the entry outputs of the target)
+    pub outputs: Vec<B>,
+}
+
+/// [`Machine<S, A, B>`] describes a state machine with states of type `S` reading inputs of type `A` and writing outputs of type `B`.
+///
+/// The machine starts in the initial state and writes the entry outputs of that state.
+/// Then, on every input, it looks up the first transition from the current state triggered by that input.
+/// If there is such a transition, the machine writes the outputs of the transition followed by the entry outputs of the target and moves to the target.
+/// Otherwise, the input is ignored, that is, the machine neither writes anything nor changes its state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Machine<S, A, B> {
+    /// state the machine starts in
+    initial: S,
+    /// outputs written on entering states
+    entries: Vec<(S, Vec<B>)>,
+    /// transition table
+    transitions: Vec<Transition<S, A, B>>,
+}
+
+impl<S, A, B> Machine<S, A, B> {
+    /// Create a machine without entry outputs and without transitions.
+    /// - `initial` is the state the machine starts in.
+    ///
+    /// # Examples
+    ///
+    /// Creating a machine which stays in its initial state forever:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine: Machine<(), bool, bool> = Machine::new(());
+    /// ```
+    pub const fn new(initial: S) -> Self {
+        Self {
+            initial,
+            entries: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Add outputs to be written on entering a certain state.
+    /// - `state` is the state.
+    /// - `outputs` are the outputs.
+    ///
+    /// Outputs added for the same state before are kept and written first.
+    ///
+    /// # Examples
+    ///
+    /// Letting a light report being on when entering the state `On`:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// #[derive(Clone, PartialEq)]
+    /// enum Light {
+    ///     On,
+    ///     Off,
+    /// }
+    ///
+    /// let machine: Machine<Light, (), bool> = Machine::new(Light::Off).entry(Light::On, [true]);
+    /// ```
+    pub fn entry<T>(mut self, state: S, outputs: T) -> Self
+    where
+        T: IntoIterator<Item = B>,
+    {
+        self.entries.push((state, outputs.into_iter().collect()));
+        self
+    }
+
+    /// Add a row to the transition table.
+    /// - `source` is the state the transition starts in.
+    /// - `input` is the input triggering the transition.
+    /// - `target` is the state the transition ends in.
+    /// - `outputs` are the outputs written when taking the transition.
+    ///
+    /// Rows added before take precedence over rows added later.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on and off by pushing a button:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// #[derive(Clone, PartialEq)]
+    /// enum Light {
+    ///     On,
+    ///     Off,
+    /// }
+    ///
+    /// let machine = Machine::new(Light::Off)
+    ///     .transition(Light::Off, (), Light::On, [true])
+    ///     .transition(Light::On, (), Light::Off, [false]);
+    /// ```
+    pub fn transition<T>(mut self, source: S, input: A, target: S, outputs: T) -> Self
+    where
+        T: IntoIterator<Item = B>,
+    {
+        self.transitions.push(Transition {
+            source,
+            input,
+            target,
+            outputs: outputs.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Return the state the machine starts in.
+    pub const fn initial(&self) -> &S {
+        &self.initial
+    }
+
+    /// Return the transition table.
+    pub fn transitions(&self) -> &[Transition<S, A, B>] {
+        &self.transitions
+    }
+
+    /// Return all states of the machine, that is, the initial state and all states mentioned by entry outputs or transitions, in order of appearance and without duplicates.
+    ///
+    /// # Examples
+    ///
+    /// Listing the states of a light:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine: Machine<&str, (), ()> = Machine::new("off").transition("off", (), "on", []);
+    ///
+    /// assert_eq!(machine.states(), vec![&"off", &"on"]);
+    /// ```
+    pub fn states(&self) -> Vec<&S>
+    where
+        S: PartialEq,
+    {
+        let mentioned = self.entries.iter().map(|(state, _)| state).chain(
+            self.transitions
+                .iter()
+                .flat_map(|transition| [&transition.source, &transition.target]),
+        );
+
+        list_states(&self.initial, mentioned)
+    }
+
+    /// Return the outputs written on entering a certain state.
+    /// - `state` is the state.
+    pub fn entry_outputs<'s>(&'s self, state: &'s S) -> impl Iterator<Item = &'s B>
+    where
+        S: PartialEq,
+    {
+        self.entries
+            .iter()
+            .filter(move |(s, _)| s == state)
+            .flat_map(|(_, outputs)| outputs)
+    }
+
+    /// Look up the transition taken in a certain state on a certain input.
+    /// - `state` is the current state.
+    /// - `input` is the input.
+    ///
+    /// The result is `None` if the input is ignored in that state.
+    ///
+    /// # Examples
+    ///
+    /// Checking where a light goes when pushing the button:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine: Machine<&str, (), ()> = Machine::new("off").transition("off", (), "on", []);
+    ///
+    /// assert_eq!(machine.step(&"off", &()).map(|t| t.target), Some("on"));
+    /// assert_eq!(machine.step(&"on", &()), None);
+    /// ```
+    pub fn step(&self, state: &S, input: &A) -> Option<&Transition<S, A, B>>
+    where
+        S: PartialEq,
+        A: PartialEq,
+    {
+        self.transitions
+            .iter()
+            .find(|transition| transition.source == *state && transition.input == *input)
+    }
+
+    /// Run the machine on a sequence of inputs without lowering it to a stream processor.
+    /// - `inputs` are the inputs.
+    ///
+    /// The result consists of the outputs written (including the entry outputs of the initial state) and the final state.
+    ///
+    /// # Examples
+    ///
+    /// Pushing the button of a light twice:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine = Machine::new("off")
+    ///     .transition("off", (), "on", [true])
+    ///     .transition("on", (), "off", [false]);
+    ///
+    /// assert_eq!(machine.run([(), ()]), (vec![true, false], &"off"));
+    /// ```
+    pub fn run<T>(&self, inputs: T) -> (Vec<B>, &S)
+    where
+        S: PartialEq,
+        A: PartialEq,
+        B: Clone,
+        T: IntoIterator<Item = A>,
+    {
+        let mut state = &self.initial;
+        let mut outputs = self.entry_outputs(state).cloned().collect::<Vec<_>>();
+
+        for input in inputs {
+            if let Some(transition) = self.step(state, &input) {
+                state = &transition.target;
+                outputs.extend(transition.outputs.iter().cloned());
+                outputs.extend(self.entry_outputs(state).cloned());
+            }
+        }
+
+        (outputs, state)
+    }
+
+    /// Lower `self` to a stream processor behaving like the machine.
+    ///
+    /// # Examples
+    ///
+    /// Switching a light on and off by pushing a button:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine = Machine::new("off")
+    ///     .transition("off", (), "on", [true])
+    ///     .transition("on", (), "off", [false]);
+    ///
+    /// let result = machine.into_stream_processor().eval_iter([(); 3]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(result, vec![true, false, true]);
+    /// ```
+    pub fn into_stream_processor<'a>(self) -> StreamProcessor<'a, A, B>
+    where
+        S: PartialEq + Clone + 'a,
+        A: PartialEq + 'a,
+        B: Clone + 'a,
+    {
+        let initial = self.initial.clone();
+        let outputs = self.entry_outputs(&initial).cloned().collect::<Vec<_>>();
+
+        let machine = Rc::new(self);
+        puts(outputs, move || lower(machine, initial))
+    }
+
+    /// Export `self` as graph in the DOT-language of [Graphviz](https://graphviz.org).
+    ///
+    /// Every state becomes a node labeled with its entry outputs and every transition an edge labeled with its input and outputs.
+    ///
+    /// # Examples
+    ///
+    /// Drawing a light:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine = Machine::new("off").transition("off", (), "on", [true]);
+    ///
+    /// assert!(machine.to_dot().contains("s0 -> s1 [label=\"() / [true]\"];"));
+    /// ```
+    pub fn to_dot(&self) -> String
+    where
+        S: PartialEq + fmt::Debug,
+        A: fmt::Debug,
+        B: fmt::Debug,
+    {
+        format!("{}", Dot(self))
+    }
+
+    /// Export `self` as state diagram in the language of [Mermaid](https://mermaid.js.org).
+    ///
+    /// Every state is described by its entry outputs and every transition is labeled with its input and outputs.
+    ///
+    /// # Examples
+    ///
+    /// Drawing a light:
+    ///
+    /// ```
+    /// use rspl::machines::Machine;
+    ///
+    /// let machine = Machine::new("off").transition("off", (), "on", [true]);
+    ///
+    /// assert!(machine.to_mermaid().contains("s0 --> s1 : () / [true]"));
+    /// ```
+    pub fn to_mermaid(&self) -> String
+    where
+        S: PartialEq + fmt::Debug,
+        A: fmt::Debug,
+        B: fmt::Debug,
+    {
+        format!("{}", Mermaid(self))
+    }
+}
+
+/// Construct the stream processor behaving like a machine in a certain state.
+/// - `machine` is the machine.
+/// - `state` is the current state.
+fn lower<'a, S, A, B>(machine: Rc<Machine<S, A, B>>, state: S) -> StreamProcessor<'a, A, B>
+where
+    S: PartialEq + Clone + 'a,
+    A: PartialEq + 'a,
+    B: Clone + 'a,
+{
+    StreamProcessor::Get(box_fn(move |a: A| {
+        let Some(transition) = machine.step(&state, &a) else {
+            return lower(machine, state);
+        };
+
+        let target = transition.target.clone();
+        let outputs = transition
+            .outputs
+            .iter()
+            .chain(machine.entry_outputs(&target))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        puts(outputs, move || lower(machine, target))
+    }))
+}
+
+/// Construct the stream processor which writes some outputs and continues with another stream processor afterwards.
+/// - `outputs` are the outputs.
+/// - `lazy_sp` is the thunk of the stream processor to continue with.
+pub(crate) fn puts<'a, A: 'a, B: 'a, T>(outputs: Vec<B>, lazy_sp: T) -> StreamProcessor<'a, A, B>
+where
+    T: FnOnce() -> StreamProcessor<'a, A, B> + 'a,
+{
+    puts_iter(outputs.into_iter(), lazy_sp)
+}
+
+/// Construct the stream processor which writes the remaining outputs of an iterator and continues with another stream processor afterwards.
+/// - `outputs` is the iterator of the outputs which is passed on instead of collecting the rest of the outputs on every write.
+/// - `lazy_sp` is the thunk of the stream processor to continue with.
+fn puts_iter<'a, A: 'a, B: 'a, T>(
+    mut outputs: vec::IntoIter<B>,
+    lazy_sp: T,
+) -> StreamProcessor<'a, A, B>
+where
+    T: FnOnce() -> StreamProcessor<'a, A, B> + 'a,
+{
+    match outputs.next() {
+        Some(b) => StreamProcessor::Put(b, box_lazy(move || puts_iter(outputs, lazy_sp))),
+        None => lazy_sp(),
+    }
+}
+
+impl<S, A, B> fmt::Display for Machine<S, A, B>
+where
+    S: fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print the initial state, the entry outputs and the transition table line by line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "initial: {:?}", self.initial)?;
+        for (state, outputs) in &self.entries {
+            writeln!(f, "entry {:?} / {:?}", state, outputs)?;
+        }
+        for transition in &self.transitions {
+            writeln!(
+                f,
+                "{:?} --{:?} / {:?}--> {:?}",
+                transition.source, transition.input, transition.outputs, transition.target
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// [`Dot`] wraps a [`Machine`] to print it in the DOT-language.
+struct Dot<'m, S, A, B>(&'m Machine<S, A, B>);
+
+impl<S, A, B> fmt::Display for Dot<'_, S, A, B>
+where
+    S: PartialEq + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print the states as nodes and the transitions as edges.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let machine = self.0;
+        let states = machine.states();
+
+        writeln!(f, "digraph {{")?;
+        writeln!(f, "    initial [shape=point];")?;
+        for (id, state) in states.iter().enumerate() {
+            let mut lines = Vec::from([format!("{:?}", state)]);
+            let entry = machine.entry_outputs(state).collect::<Vec<_>>();
+            if !entry.is_empty() {
+                lines.push(format!("entry / {:?}", entry));
+            }
+            writeln!(f, "    s{} [label=\"{}\"];", id, escape_dot(&lines))?;
+        }
+        writeln!(f, "    initial -> s0;")?;
+        for transition in &machine.transitions {
+            writeln!(
+                f,
+                "    s{} -> s{} [label=\"{}\"];",
+                state_id(&states, &transition.source),
+                state_id(&states, &transition.target),
+                escape_dot(&[trigger_label(
+                    &format!("{:?}", transition.input),
+                    &transition.outputs
+                )])
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// [`Mermaid`] wraps a [`Machine`] to print it in the language of Mermaid.
+struct Mermaid<'m, S, A, B>(&'m Machine<S, A, B>);
+
+impl<S, A, B> fmt::Display for Mermaid<'_, S, A, B>
+where
+    S: PartialEq + fmt::Debug,
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    /// Print the states with their descriptions and the transitions.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let machine = self.0;
+        let states = machine.states();
+
+        writeln!(f, "stateDiagram-v2")?;
+        for (id, state) in states.iter().enumerate() {
+            writeln!(
+                f,
+                "    state \"{}\" as s{}",
+                escape_mermaid(&format!("{:?}", state)),
+                id
+            )?;
+            let entry = machine.entry_outputs(state).collect::<Vec<_>>();
+            if !entry.is_empty() {
+                writeln!(
+                    f,
+                    "    s{} : entry / {}",
+                    id,
+                    escape_mermaid(&format!("{:?}", entry))
+                )?;
+            }
+        }
+        writeln!(f, "    [*] --> s0")?;
+        for transition in &machine.transitions {
+            writeln!(
+                f,
+                "    s{} --> s{} : {}",
+                state_id(&states, &transition.source),
+                state_id(&states, &transition.target),
+                escape_mermaid(&trigger_label(
+                    &format!("{:?}", transition.input),
+                    &transition.outputs
+                ))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// List the initial state of a machine followed by the states mentioned by the machine in order of appearance and without duplicates.
+/// - `initial` is the initial state.
+/// - `mentioned` are the mentioned states.
+pub(crate) fn list_states<'s, S, T>(initial: &'s S, mentioned: T) -> Vec<&'s S>
+where
+    S: PartialEq,
+    T: IntoIterator<Item = &'s S>,
+{
+    let mut states = Vec::from([initial]);
+    for state in mentioned {
+        if !states.contains(&state) {
+            states.push(state);
+        }
+    }
+    states
+}
+
+/// Return the index of a state in a list of states which serves as identifier in the exports.
+/// - `states` are the states.
+/// - `state` is the state.
+///
+/// # Panics
+///
+/// A panic occurs if the state is not listed.
+pub(crate) fn state_id<S: PartialEq>(states: &[&S], state: &S) -> usize {
+    states
+        .iter()
+        .position(|s| *s == state)
+        .expect("all states are listed")
+}
+
+/// Label a transition by its trigger followed by its outputs if there are any.
+/// - `trigger` is the description of the trigger of the transition.
+/// - `outputs` are the outputs of the transition.
+pub(crate) fn trigger_label<B: fmt::Debug>(trigger: &str, outputs: &[B]) -> String {
+    if outputs.is_empty() {
+        String::from(trigger)
+    } else {
+        format!("{} / {:?}", trigger, outputs)
+    }
+}
+
+/// Escape some lines of text such that they can be used as one quoted string of the DOT-language.
+/// - `lines` are the lines.
+pub(crate) fn escape_dot<T: AsRef<str>>(lines: &[T]) -> String {
+    lines
+        .iter()
+        .map(|line| line.as_ref().replace('\\', "\\\\").replace('"', "\\\""))
+        .collect::<Vec<_>>()
+        .join("\\n")
+}
+
+/// Escape a text such that it can be used in the language of Mermaid.
+/// - `text` is the text.
+pub(crate) fn escape_mermaid(text: &str) -> String {
+    text.replace(';', "#59;").replace('"', "#quot;")
+}
+
+/// Define state machines as stream processors with a small domain-specific language.
+///
+/// The macro expands to one function per state returning the [`StreamProcessor`] which behaves like the machine in that state, just as the type-state pattern of the [pelican example](https://github.com/shtsoft/rspl/blob/master/examples/pelican.md) does by hand.
+/// Its input starts with the input type and the output type of the machine separated by `=>` and terminated by `;`.
+/// Then come the states where each state is written as `state name { ... }` (optionally preceded by a visibility) and its body consists of the following items separated by `,`:
+/// - `entry put(x) put(y) ...` determines the outputs written whenever the state is the target of a transition.
+/// - `pattern => put(x) put(y) ... -> target` is a transition writing the outputs and moving to the state `target` if the input matches `pattern`.
+///   The pattern can have a guard (`pattern if guard => ...`) and its variables can be used in the outputs.
+///   The special target `self` stays in the current state without writing the entry outputs again.
+/// - `state name { ... }` is a substate which inherits all transitions of its parent (and ancestors) unless it overrides them by transitions for the same inputs.
+///
+/// A state with substates has to name its initial substate like `state name > initial { ... }`.
+/// Then, entering it writes its entry outputs followed by the ones of the initial substate.
+/// The functions of the substates are local to the function of their parent.
+/// So, substates can only be targeted from within their parent, but substates of different parents can have the same name.
+/// Inputs no transition matches are ignored.
+///
+/// # Examples
+///
+/// The keyboard of the [events integration test](https://github.com/shtsoft/rspl/blob/master/tests/events.rs) where pressing `Esc` resets everything:
+///
+/// ```
+/// use rspl::machine;
+/// use rspl::runners::PushRunner;
+///
+/// #[derive(Copy, Clone)]
+/// enum Event {
+///     ShiftDepressed,
+///     ShiftReleased,
+///     Key(char),
+///     Esc,
+/// }
+///
+/// use Event::*;
+///
+/// machine! {
+///     Event => char;
+///
+///     state keyboard > default {
+///         Esc => put('!') -> default,
+///
+///         state default {
+///             ShiftDepressed => -> shifted,
+///             Key(c) => put(c) -> self,
+///         },
+///         state shifted {
+///             ShiftReleased => -> default,
+///             Key(c) => put(c.to_ascii_uppercase()) -> self,
+///         },
+///     }
+/// }
+///
+/// let mut runner = PushRunner::new(keyboard());
+///
+/// let mut output = String::new();
+/// for event in [Key('a'), ShiftDepressed, Key('b'), Esc, Key('c')] {
+///     output.extend(runner.feed(event));
+/// }
+///
+/// assert_eq!(output, "aB!c");
+/// ```
+#[macro_export]
+macro_rules! machine {
+    // This rule writes some outputs and continues with some stream processor.
+    (@puts [] $next:expr) => {
+        $next
+    };
+    (@puts [$first:expr $(, $rest:expr)*] $next:expr) => {
+        $crate::StreamProcessor::put($first, move || $crate::machine!(@puts [$($rest),*] $next))
+    };
+
+    // This rule continues with the target of a transition.
+    // The loop of a state is named such that it cannot collide with the name of a state.
+    (@goto self) => {
+        __machine_listen()
+    };
+    (@goto $target:ident) => {
+        $target()
+    };
+
+    // These rules munch the body of a state collecting entry outputs, transitions and substates.
+    (@state $header:tt $inherited:tt [$($entry:tt)*] $arms:tt $children:tt
+        entry $(put($out:expr))* $(, $($rest:tt)*)?
+    ) => {
+        $crate::machine!(@state $header $inherited [$($entry)* $($out,)*] $arms $children
+            $($($rest)*)?
+        );
+    };
+    (@state $header:tt $inherited:tt $entry:tt $arms:tt [$($child:tt)*]
+        $cvis:vis state $cname:ident $(> $cinit:ident)? { $($cbody:tt)* } $(, $($rest:tt)*)?
+    ) => {
+        $crate::machine!(@state $header $inherited $entry $arms
+            [$($child)* {[$cvis] $cname [$($cinit)?] [$($cbody)*]}]
+            $($($rest)*)?
+        );
+    };
+    (@state $header:tt $inherited:tt $entry:tt [$($arm:tt)*] $children:tt
+        $pat:pat $(if $guard:expr)? => $(put($out:expr))* -> $target:ident $(, $($rest:tt)*)?
+    ) => {
+        $crate::machine!(@state $header $inherited $entry
+            [$($arm)* $pat $(if $guard)? => $crate::machine!(@puts [$($out),*] $crate::machine!(@goto $target)),]
+            $children
+            $($($rest)*)?
+        );
+    };
+
+    // This rule emits a state without substates.
+    (@state [[$vis:vis] $name:ident [$($init:ident)?] [$in:ty, $out:ty]] [$($inherited:tt)*] [$($entry:expr,)*] [$($arm:tt)*] []) => {
+        $vis fn $name<'a>() -> $crate::StreamProcessor<'a, $in, $out> {
+            fn __machine_listen<'a>() -> $crate::StreamProcessor<'a, $in, $out> {
+                $crate::StreamProcessor::get(|input: $in| {
+                    #[allow(unreachable_patterns)]
+                    match input {
+                        $($arm)*
+                        $($inherited)*
+                        _ => __machine_listen(),
+                    }
+                })
+            }
+
+            $crate::machine!(@puts [$($entry),*] __machine_listen())
+        }
+    };
+    // This rule emits a state with substates.
+    (@state [[$vis:vis] $name:ident [$($init:ident)?] [$in:ty, $out:ty]] [$($inherited:tt)*] [$($entry:expr,)*] [$($arm:tt)*] [$($child:tt)+]) => {
+        $crate::machine!(@enter [$vis] $name [$in, $out] [$($entry),*] [$($init)?] [$($arm)* $($inherited)*] $($child)+);
+    };
+
+    // This rule emits the substates of a state which inherit the transitions of the state.
+    (@children $types:tt $inherited:tt $({[$cvis:vis] $cname:ident [$($cinit:ident)?] [$($cbody:tt)*]})+) => {
+        $(
+            $crate::machine!(@state [[$cvis] $cname [$($cinit)?] $types] $inherited [] [] [] $($cbody)*);
+        )+
+    };
+
+    // These rules emit the entering of a state with substates where the substates are local to it.
+    (@enter [$vis:vis] $name:ident [$in:ty, $out:ty] [$($entry:expr),*] [] $inherited:tt $($child:tt)+) => {
+        compile_error!(concat!("the state `", stringify!($name), "` has substates but no initial substate"));
+    };
+    (@enter [$vis:vis] $name:ident [$in:ty, $out:ty] [$($entry:expr),*] [$init:ident] $inherited:tt $($child:tt)+) => {
+        $vis fn $name<'a>() -> $crate::StreamProcessor<'a, $in, $out> {
+            $crate::machine!(@children [$in, $out] $inherited $($child)+);
+
+            $crate::machine!(@puts [$($entry),*] $init())
+        }
+    };
+
+    // These rules munch the top-level states.
+    (@top [$in:ty, $out:ty]) => {};
+    (@top [$in:ty, $out:ty]
+        $vis:vis state $name:ident $(> $init:ident)? { $($body:tt)* } $(, $($rest:tt)*)?
+    ) => {
+        $crate::machine!(@state [[$vis] $name [$($init)?] [$in, $out]] [] [] [] [] $($body)*);
+        $crate::machine!(@top [$in, $out] $($($rest)*)?);
+    };
+
+    ($in:ty => $out:ty; $($states:tt)*) => {
+        $crate::machine!(@top [$in, $out] $($states)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runners::PushRunner;
+
+    use alloc::format;
+    use alloc::vec;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Light {
+        Off,
+        On,
+        Broken,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Event {
+        Push,
+        Kick,
+    }
+
+    fn light() -> Machine<Light, Event, &'static str> {
+        Machine::new(Light::Off)
+            .entry(Light::Off, ["dark"])
+            .entry(Light::On, ["bright"])
+            .transition(Light::Off, Event::Push, Light::On, ["click"])
+            .transition(Light::On, Event::Push, Light::Off, ["click"])
+            .transition(Light::On, Event::Kick, Light::Broken, ["crash"])
+    }
+
+    #[test]
+    fn test_states() {
+        assert_eq!(
+            light().states(),
+            vec![&Light::Off, &Light::On, &Light::Broken]
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        let machine = light();
+
+        let transition = machine.step(&Light::On, &Event::Kick).unwrap();
+        assert_eq!(transition.target, Light::Broken);
+        assert_eq!(transition.outputs, vec!["crash"]);
+
+        assert_eq!(machine.step(&Light::Off, &Event::Kick), None);
+        assert_eq!(machine.step(&Light::Broken, &Event::Push), None);
+    }
+
+    crate::machine! {
+        usize => &'static str;
+
+        state counter > low {
+            0 => put("reset") -> low,
+            n if n > 100 => put("overflow") -> low,
+
+            state low {
+                entry put("low"),
+                n if n > 10 => put("up") -> high,
+                1 => put("stay") -> self,
+            },
+            state high {
+                entry put("high") put("!"),
+                1 => -> low,
+            },
+        }
+    }
+
+    crate::machine! {
+        bool => usize;
+
+        state first > idle {
+            state idle {
+                true => put(1) -> second,
+            },
+        },
+        state second > idle {
+            state idle {
+                true => put(2) -> first,
+            },
+        },
+    }
+
+    crate::machine! {
+        bool => &'static str;
+
+        state speak {
+            entry put("speak"),
+            true => -> listen,
+            false => -> self,
+        },
+        state listen {
+            entry put("listen"),
+            true => -> speak,
+        },
+    }
+
+    #[test]
+    fn test_machine() {
+        let mut runner = PushRunner::new(counter());
+
+        let mut outputs = runner.pending().collect::<Vec<_>>();
+        for n in [1, 11, 1, 11, 200, 0, 5] {
+            outputs.extend(runner.feed(n));
+        }
+
+        assert_eq!(
+            outputs,
+            vec![
+                "low", "stay", "up", "high", "!", "low", "up", "high", "!", "overflow", "low",
+                "reset", "low"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_machine_scopes() {
+        let mut runner = PushRunner::new(first());
+
+        let mut outputs = Vec::new();
+        for input in [true, false, true, true] {
+            outputs.extend(runner.feed(input));
+        }
+
+        assert_eq!(outputs, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_machine_listen() {
+        let mut runner = PushRunner::new(speak());
+
+        let mut outputs = runner.pending().collect::<Vec<_>>();
+        for input in [false, true, false, true] {
+            outputs.extend(runner.feed(input));
+        }
+
+        assert_eq!(outputs, vec!["speak", "listen", "speak"]);
+    }
+
+    #[test]
+    fn test_run() {
+        let machine = light();
+
+        let (outputs, state) = machine.run([Event::Kick, Event::Push, Event::Kick, Event::Push]);
+        assert_eq!(outputs, vec!["dark", "click", "bright", "crash"]);
+        assert_eq!(*state, Light::Broken);
+    }
+
+    #[test]
+    fn test_into_stream_processor() {
+        const INPUTS: [Event; 4] = [Event::Push, Event::Push, Event::Kick, Event::Push];
+
+        let mut runner = PushRunner::new(light().into_stream_processor());
+
+        let mut outputs = runner.pending().collect::<Vec<_>>();
+        for event in INPUTS {
+            outputs.extend(runner.feed(event));
+        }
+
+        assert_eq!(outputs, light().run(INPUTS).0);
+    }
+
+    #[test]
+    fn test_display() {
+        let machine = Machine::new(Light::Off)
+            .entry(Light::On, [true])
+            .transition(Light::Off, Event::Push, Light::On, []);
+
+        assert_eq!(
+            format!("{}", machine),
+            "initial: Off\nentry On / [true]\nOff --Push / []--> On\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            light().to_dot(),
+            "digraph {
+    initial [shape=point];
+    s0 [label=\"Off\\nentry / [\\\"dark\\\"]\"];
+    s1 [label=\"On\\nentry / [\\\"bright\\\"]\"];
+    s2 [label=\"Broken\"];
+    initial -> s0;
+    s0 -> s1 [label=\"Push / [\\\"click\\\"]\"];
+    s1 -> s0 [label=\"Push / [\\\"click\\\"]\"];
+    s1 -> s2 [label=\"Kick / [\\\"crash\\\"]\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        assert_eq!(
+            light().to_mermaid(),
+            "stateDiagram-v2
+    state \"Off\" as s0
+    s0 : entry / [#quot;dark#quot;]
+    state \"On\" as s1
+    s1 : entry / [#quot;bright#quot;]
+    state \"Broken\" as s2
+    [*] --> s0
+    s0 --> s1 : Push / [#quot;click#quot;]
+    s1 --> s0 : Push / [#quot;click#quot;]
+    s1 --> s2 : Kick / [#quot;crash#quot;]
+"
+        );
+    }
+}
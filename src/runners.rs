@@ -0,0 +1,498 @@
+//! This module provides ways to run stream processors other than evaluating them on an input stream.
+//! In particular, there are runners which are driven from outside, for example, from the callbacks of a foreign event loop.
+
+use super::closures::{Apply, Force};
+use super::streams::PollStream;
+use super::StreamProcessor;
+
+use alloc::collections::VecDeque;
+
+use core::convert::Infallible;
+use core::task::Poll;
+
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task::Context;
+
+/// [`PushRunner<A, B, R>`] holds a stream processor together with the input pushed to it but not yet read.
+/// Other than with [`eval`](`StreamProcessor::eval`) it is not the runner which pulls input from a stream but the user who pushes input to the runner.
+pub struct PushRunner<'a, A: 'a, B, R = Infallible> {
+    /// stream processor in its current state where `None` is only a transitional placeholder
+    sp: Option<StreamProcessor<'a, A, B, R>>,
+    /// input pushed to the runner but not yet read by the stream processor
+    inputs: VecDeque<A>,
+}
+
+impl<'a, A, B, R> PushRunner<'a, A, B, R> {
+    /// Create a runner for a stream processor.
+    /// - `sp` is the stream processor to be run.
+    ///
+    /// # Examples
+    ///
+    /// Creating a runner for the stream processor negating bools:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::runners::PushRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    ///
+    /// let runner = PushRunner::new(negate);
+    /// ```
+    pub const fn new(sp: StreamProcessor<'a, A, B, R>) -> Self {
+        Self {
+            sp: Some(sp),
+            inputs: VecDeque::new(),
+        }
+    }
+
+    /// Push an input to the runner and return the outputs which become available thereby.
+    /// - `a` is the input.
+    ///
+    /// The outputs are produced lazily as the returned iterator is consumed.
+    /// Outputs which are not consumed remain pending and so does input which is not read yet.
+    /// Input pushed after the stream processor has terminated is thrown away.
+    ///
+    /// # Examples
+    ///
+    /// Negating a bool from within a callback:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::runners::PushRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    ///
+    /// let mut runner = PushRunner::new(negate);
+    ///
+    /// let mut callback = |b| runner.feed(b).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(callback(true), vec![false]);
+    /// ```
+    pub fn feed(&mut self, a: A) -> Outputs<'_, 'a, A, B, R> {
+        if self.result().is_none() {
+            self.inputs.push_back(a);
+        }
+        self.pending()
+    }
+
+    /// Return the outputs which are available without pushing further input to the runner.
+    ///
+    /// The outputs are produced lazily as the returned iterator is consumed.
+    ///
+    /// # Examples
+    ///
+    /// Taking the first outputs of a generator without any input:
+    ///
+    /// ```
+    /// use rspl::combinators::generate;
+    /// use rspl::runners::PushRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let ascending: StreamProcessor<(), usize> = generate(|n| (n, n + 1), 0);
+    ///
+    /// let mut runner = PushRunner::new(ascending);
+    ///
+    /// assert_eq!(runner.pending().take(3).collect::<Vec<_>>(), vec![0, 1, 2]);
+    /// ```
+    pub fn pending(&mut self) -> Outputs<'_, 'a, A, B, R> {
+        Outputs { runner: self }
+    }
+
+    /// Return the result of the stream processor if it has terminated already.
+    ///
+    /// # Examples
+    ///
+    /// Observing the termination of a stream processor:
+    ///
+    /// ```
+    /// use rspl::runners::PushRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let sp: StreamProcessor<bool, (), bool> = StreamProcessor::get(StreamProcessor::Done);
+    ///
+    /// let mut runner = PushRunner::new(sp);
+    /// assert_eq!(runner.result(), None);
+    ///
+    /// runner.feed(true).for_each(drop);
+    /// assert_eq!(runner.result(), Some(&true));
+    /// ```
+    pub fn result(&self) -> Option<&R> {
+        match self.sp {
+            Some(StreamProcessor::Done(ref r)) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+/// [`Outputs`] is the iterator over the outputs of a [`PushRunner`] which are available with the input pushed so far.
+pub struct Outputs<'r, 'a, A: 'a, B, R> {
+    /// runner producing the outputs
+    runner: &'r mut PushRunner<'a, A, B, R>,
+}
+
+impl<'r, 'a, A, B, R> Iterator for Outputs<'r, 'a, A, B, R> {
+    type Item = B;
+
+    /// Step the stream processor of the runner until it writes something, needs more input or terminates.
+    fn next(&mut self) -> Option<B> {
+        loop {
+            match self.runner.sp.take()? {
+                StreamProcessor::Get(f) => match self.runner.inputs.pop_front() {
+                    Some(a) => self.runner.sp = Some(f.apply(a)),
+                    None => {
+                        self.runner.sp = Some(StreamProcessor::Get(f));
+                        return None;
+                    }
+                },
+                StreamProcessor::Put(b, lazy_sp) => {
+                    self.runner.sp = Some(lazy_sp.force());
+                    return Some(b);
+                }
+                StreamProcessor::Done(r) => {
+                    self.runner.sp = Some(StreamProcessor::Done(r));
+                    self.runner.inputs.clear();
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// [`PollRunner<A, B, S, R>`] runs a stream processor on a source which is polled for input such that running never blocks.
+pub struct PollRunner<'a, A: 'a, B, S, R = Infallible> {
+    /// runner holding the stream processor
+    runner: PushRunner<'a, A, B, R>,
+    /// source of input
+    source: S,
+}
+
+impl<'a, A, B, S, R> PollRunner<'a, A, B, S, R>
+where
+    S: PollStream<A>,
+{
+    /// Create a runner for a stream processor polling a certain source.
+    /// - `sp` is the stream processor to be run.
+    /// - `source` is the source of input.
+    ///
+    /// # Examples
+    ///
+    /// Creating a runner negating the bools received by a channel:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::runners::PollRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    /// let (tx, rx) = crossbeam::channel::unbounded();
+    ///
+    /// let runner = PollRunner::new(negate, rx);
+    /// ```
+    pub const fn new(sp: StreamProcessor<'a, A, B, R>, source: S) -> Self {
+        Self {
+            runner: PushRunner::new(sp),
+            source,
+        }
+    }
+
+    /// Poll the next output of the stream processor.
+    ///
+    /// The result is
+    /// - `Poll::Ready(Some(b))` if the stream processor writes `b` (possibly after reading input from the source).
+    /// - `Poll::Ready(None)` if the stream processor has terminated or needs input from a source which has ended.
+    /// - `Poll::Pending` if the stream processor needs input but the source has none yet.
+    ///
+    /// So, outputs which can be produced without further input are never held back.
+    ///
+    /// # Panics
+    ///
+    /// A panic may occur if
+    /// - the stream processor contains Rust-terms which can panic.
+    /// - the respective implementation of [`PollStream::poll`] can panic.
+    ///
+    /// # Examples
+    ///
+    /// Checking for the negation of a bool without blocking:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::runners::PollRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// use core::task::Poll;
+    ///
+    /// let negate: StreamProcessor<_, _> = map(|b: bool| !b);
+    /// let (tx, rx) = crossbeam::channel::unbounded();
+    ///
+    /// let mut runner = PollRunner::new(negate, rx);
+    /// assert_eq!(runner.poll(), Poll::Pending);
+    ///
+    /// tx.send(true).unwrap();
+    /// assert_eq!(runner.poll(), Poll::Ready(Some(false)));
+    /// ```
+    pub fn poll(&mut self) -> Poll<Option<B>> {
+        loop {
+            if let Some(b) = self.runner.pending().next() {
+                return Poll::Ready(Some(b));
+            }
+
+            if self.runner.result().is_some() {
+                return Poll::Ready(None);
+            }
+
+            match self.source.poll() {
+                Poll::Ready(Some(a)) => self.runner.inputs.push_back(a),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Return the result of the stream processor if it has terminated already.
+    ///
+    /// # Examples
+    ///
+    /// Observing the termination of a stream processor:
+    ///
+    /// ```
+    /// use rspl::runners::PollRunner;
+    /// use rspl::StreamProcessor;
+    ///
+    /// let (tx, rx) = crossbeam::channel::unbounded();
+    ///
+    /// let sp: StreamProcessor<bool, (), bool> = StreamProcessor::get(StreamProcessor::Done);
+    ///
+    /// let mut runner = PollRunner::new(sp, rx);
+    ///
+    /// tx.send(true).unwrap();
+    /// runner.poll();
+    /// assert_eq!(runner.result(), Some(&true));
+    /// ```
+    pub fn result(&self) -> Option<&R> {
+        self.runner.result()
+    }
+}
+
+/// [`AsyncRunner<A, B, S, R>`] runs a stream processor on an asynchronous input stream of type `S` yielding an asynchronous output stream (see [`eval_async`](`StreamProcessor::eval_async`)).
+#[cfg(feature = "async")]
+pub struct AsyncRunner<'a, A: 'a, B, S, R = Infallible> {
+    /// stream processor in its current state where `None` means that running has finished
+    sp: Option<StreamProcessor<'a, A, B, R>>,
+    /// asynchronous input stream
+    stream: S,
+}
+
+#[cfg(feature = "async")]
+impl<'a, A, B, S, R> AsyncRunner<'a, A, B, S, R> {
+    /// Create a runner for a stream processor on an asynchronous input stream.
+    /// - `sp` is the stream processor to be run.
+    /// - `stream` is the asynchronous input stream.
+    pub(crate) const fn new(sp: StreamProcessor<'a, A, B, R>, stream: S) -> Self {
+        Self {
+            sp: Some(sp),
+            stream,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, A, B, S, R> futures_core::Stream for AsyncRunner<'a, A, B, S, R>
+where
+    B: Unpin,
+    S: futures_core::Stream<Item = A> + Unpin,
+    R: Unpin,
+{
+    type Item = B;
+
+    /// Step the stream processor until it writes something, terminates or needs input which is not ready yet.
+    /// The output ends if the stream processor terminates or needs input from an ended input stream.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<B>> {
+        let this = self.get_mut();
+
+        loop {
+            match this.sp.take() {
+                Some(StreamProcessor::Get(f)) => match Pin::new(&mut this.stream).poll_next(cx) {
+                    Poll::Ready(Some(a)) => this.sp = Some(f.apply(a)),
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => {
+                        this.sp = Some(StreamProcessor::Get(f));
+                        return Poll::Pending;
+                    }
+                },
+                Some(StreamProcessor::Put(b, lazy_sp)) => {
+                    this.sp = Some(lazy_sp.force());
+                    return Poll::Ready(Some(b));
+                }
+                Some(StreamProcessor::Done(_)) | None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinators::{filter, map};
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_new() {
+        let sp: StreamProcessor<_, _> = map(|n: usize| n);
+        let runner = PushRunner::new(sp);
+        assert!(matches!(runner.sp, Some(StreamProcessor::Get(_))));
+        assert!(runner.inputs.is_empty());
+    }
+
+    #[test]
+    fn test_feed() {
+        let sp: StreamProcessor<_, _> = filter(|n: &usize| *n > 0);
+        let mut runner = PushRunner::new(sp);
+
+        assert_eq!(runner.feed(0).collect::<Vec<_>>(), vec![]);
+        assert_eq!(runner.feed(1).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(runner.feed(2).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn test_pending() {
+        let sp: StreamProcessor<_, _> = StreamProcessor::put(0, || {
+            StreamProcessor::put(1, || {
+                StreamProcessor::get(|n| StreamProcessor::put(n, || map(|n| n)))
+            })
+        });
+
+        let mut runner = PushRunner::new(sp);
+
+        assert_eq!(runner.pending().next(), Some(0));
+        assert_eq!(runner.feed(2).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(runner.pending().collect::<Vec<_>>(), vec![]);
+        assert_eq!(runner.feed(3).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn test_result() {
+        let sp = StreamProcessor::get(|n: usize| {
+            StreamProcessor::put(n, move || StreamProcessor::Done(n))
+        });
+
+        let mut runner = PushRunner::new(sp);
+        assert_eq!(runner.result(), None);
+
+        assert_eq!(runner.feed(1).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(runner.result(), Some(&1));
+
+        assert_eq!(runner.feed(2).collect::<Vec<_>>(), vec![]);
+        assert_eq!(runner.result(), Some(&1));
+    }
+
+    #[test]
+    fn test_feed_done() {
+        let sp: StreamProcessor<usize, (), usize> = StreamProcessor::get(StreamProcessor::Done);
+
+        let mut runner = PushRunner::new(sp);
+        runner.inputs.extend([1, 2]);
+        assert_eq!(runner.pending().next(), None);
+        assert!(runner.inputs.is_empty());
+
+        for n in 3..10 {
+            assert_eq!(runner.feed(n).next(), None);
+        }
+        assert!(runner.inputs.is_empty());
+        assert_eq!(runner.result(), Some(&1));
+    }
+
+    struct Source {
+        items: VecDeque<Option<usize>>,
+    }
+
+    impl PollStream<usize> for Source {
+        fn poll(&mut self) -> Poll<Option<usize>> {
+            match self.items.pop_front() {
+                Some(Some(n)) => Poll::Ready(Some(n)),
+                Some(None) => Poll::Pending,
+                None => Poll::Ready(None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_poll() {
+        let sp = StreamProcessor::put(0, || {
+            StreamProcessor::get(|n: usize| {
+                StreamProcessor::put(n, move || StreamProcessor::Done(n))
+            })
+        });
+        let source = Source {
+            items: VecDeque::from([None, Some(1), Some(2)]),
+        };
+
+        let mut runner = PollRunner::new(sp, source);
+        assert_eq!(runner.poll(), Poll::Ready(Some(0)));
+        assert_eq!(runner.poll(), Poll::Pending);
+        assert_eq!(runner.poll(), Poll::Ready(Some(1)));
+        assert_eq!(runner.result(), Some(&1));
+        assert_eq!(runner.poll(), Poll::Ready(None));
+
+        let source = Source {
+            items: VecDeque::from([Some(1), None]),
+        };
+
+        let sp: StreamProcessor<_, _> = map(|n: usize| n);
+        let mut runner = PollRunner::new(sp, source);
+        assert_eq!(runner.poll(), Poll::Ready(Some(1)));
+        assert_eq!(runner.poll(), Poll::Pending);
+        assert_eq!(runner.poll(), Poll::Ready(None));
+        assert_eq!(runner.result(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_poll_disconnected() {
+        let (tx, rx) = crossbeam::channel::unbounded();
+
+        let sp: StreamProcessor<_, _> = map(|n: usize| n);
+        let mut runner = PollRunner::new(sp, rx);
+        assert_eq!(runner.poll(), Poll::Pending);
+
+        tx.send(1).unwrap();
+        drop(tx);
+        assert_eq!(runner.poll(), Poll::Ready(Some(1)));
+        assert_eq!(runner.poll(), Poll::Ready(None));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_runner() {
+        use futures::executor::LocalPool;
+        use futures::future::poll_fn;
+        use futures::stream::{iter, pending, StreamExt};
+
+        let sp = StreamProcessor::put(0, || {
+            StreamProcessor::get(|n: usize| {
+                StreamProcessor::put(n, move || StreamProcessor::Done(n))
+            })
+        });
+
+        let mut pool = LocalPool::new();
+        let outputs = pool.run_until(AsyncRunner::new(sp, iter([1, 2])).collect::<Vec<_>>());
+
+        assert_eq!(outputs, vec![0, 1]);
+
+        let sp: StreamProcessor<_, _> = map(|n: usize| n);
+        let mut runner = AsyncRunner::new(sp, pending());
+        let sp: StreamProcessor<_, _> = StreamProcessor::put(0, || map(|n: usize| n));
+        let mut eager_runner = AsyncRunner::new(sp, pending());
+
+        assert_eq!(
+            pool.run_until(poll_fn(|cx| Poll::Ready(runner.poll_next_unpin(cx)))),
+            Poll::Pending
+        );
+        assert_eq!(pool.run_until(eager_runner.next()), Some(0));
+    }
+}
@@ -0,0 +1,478 @@
+//! This module provides an implementation of streams as subscribers of broadcasts.
+//! A broadcast passes every message sent to all of its subscribers where each subscriber has its own cursor into a shared buffer.
+//! That way several stream processors - like a logger, a controller and a monitor - can read the same input independently.
+//!
+//! Like [lazy receivers](`super::lazy_receivers`), subscribers only receive a message when the head is observed the first time.
+//! A message is kept in the buffer until all subscribers have taken the tail past it.
+//! What happens if the buffer of a bounded broadcast is full because of a slow subscriber is determined by a [`Policy`].
+
+use super::Stream;
+
+use std::cell::OnceCell;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::vec::Vec;
+
+/// [`Policy`] determines what sending to a broadcast whose buffer is full does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Block the current thread until the slowest subscriber has taken the tail.
+    Block,
+    /// Drop the oldest message such that the slowest subscribers skip it.
+    DropOldest,
+    /// Give the message back as error.
+    Error,
+}
+
+/// [`Full<X>`] is the error of sending a message of type `X` to a broadcast whose buffer is full under [`Policy::Error`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Full<X>(pub X);
+
+/// [`State<X>`] is the state shared by a broadcast of messages of type `X` and its subscribers.
+struct State<X> {
+    /// messages not yet taken the tail past by all subscribers
+    buffer: VecDeque<X>,
+    /// number of messages ever removed from the buffer
+    offset: usize,
+    /// number of messages each subscriber has taken the tail past or `None` if the subscriber is gone
+    cursors: Vec<Option<usize>>,
+    /// number of messages the buffer can hold and policy for a full buffer or `None` if the buffer can hold any number of messages
+    bound: Option<(usize, Policy)>,
+    /// whether the broadcast is gone
+    closed: bool,
+}
+
+impl<X> State<X> {
+    /// Return the number of messages ever sent.
+    fn end(&self) -> usize {
+        self.offset + self.buffer.len()
+    }
+
+    /// Remove the messages all subscribers have taken the tail past from the buffer.
+    fn collect(&mut self) {
+        let min = self.cursors.iter().flatten().min().copied();
+        while self.offset < min.unwrap_or_else(|| self.end()) {
+            self.buffer.pop_front();
+            self.offset += 1;
+        }
+    }
+}
+
+/// [`Shared<X>`] protects the state shared by a broadcast of messages of type `X` and its subscribers.
+struct Shared<X> {
+    /// state
+    state: Mutex<State<X>>,
+    /// condition variable notified whenever the state changes
+    changed: Condvar,
+}
+
+impl<X> Shared<X> {
+    /// Lock the state.
+    fn lock(&self) -> MutexGuard<'_, State<X>> {
+        self.state.lock().unwrap()
+    }
+
+    /// Block the current thread until the state changes.
+    /// - `state` is the locked state.
+    fn wait<'a>(&self, state: MutexGuard<'a, State<X>>) -> MutexGuard<'a, State<X>> {
+        self.changed.wait(state).unwrap()
+    }
+}
+
+/// [`Broadcast<X>`] abstracts broadcasts of messages of type `X`.
+pub struct Broadcast<X> {
+    /// state shared with the subscribers
+    shared: Arc<Shared<X>>,
+}
+
+impl<X> Broadcast<X> {
+    /// Create a broadcast without subscribers whose buffer can hold a certain number of messages.
+    /// - `cap` is the number of messages the buffer can hold.
+    /// - `policy` determines what sending does if the buffer is full.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if `cap` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// Creating a broadcast whose slow subscribers miss messages:
+    ///
+    /// ```
+    /// use rspl::streams::broadcasts::{Broadcast, Policy};
+    /// use rspl::streams::Stream;
+    ///
+    /// let broadcast = Broadcast::new(1, Policy::DropOldest);
+    /// let stream = broadcast.subscribe();
+    ///
+    /// broadcast.send(1).unwrap();
+    /// broadcast.send(2).unwrap();
+    ///
+    /// assert_eq!(*stream.head(), 2);
+    /// ```
+    pub fn new(cap: usize, policy: Policy) -> Self {
+        assert!(cap > 0, "the buffer must hold at least one message");
+        Self::with_bound(Some((cap, policy)))
+    }
+
+    /// Create a broadcast without subscribers whose buffer can hold any number of messages.
+    ///
+    /// # Examples
+    ///
+    /// Creating a broadcast which never blocks on sending:
+    ///
+    /// ```
+    /// use rspl::streams::broadcasts::Broadcast;
+    /// use rspl::streams::Stream;
+    ///
+    /// let broadcast = Broadcast::unbounded();
+    /// let stream = broadcast.subscribe();
+    ///
+    /// for n in 0..100 {
+    ///     broadcast.send(n).unwrap();
+    /// }
+    ///
+    /// assert_eq!(*stream.head(), 0);
+    /// ```
+    pub fn unbounded() -> Self {
+        Self::with_bound(None)
+    }
+
+    /// Create a broadcast without subscribers.
+    /// - `bound` is the number of messages the buffer can hold and the policy for a full buffer or `None` if the buffer can hold any number of messages.
+    fn with_bound(bound: Option<(usize, Policy)>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    buffer: VecDeque::new(),
+                    offset: 0,
+                    cursors: Vec::new(),
+                    bound,
+                    closed: false,
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Create a subscriber receiving the messages sent from now on.
+    ///
+    /// Note that the subscriber takes over the cursor of an unsubscribed subscriber if there is one.
+    ///
+    /// # Examples
+    ///
+    /// Feeding two stream processors with the same input:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::streams::broadcasts::Broadcast;
+    /// use rspl::streams::Stream;
+    ///
+    /// let broadcast = Broadcast::unbounded();
+    /// let logger_input = broadcast.subscribe();
+    /// let controller_input = broadcast.subscribe();
+    ///
+    /// broadcast.send(1).unwrap();
+    ///
+    /// let logger = map(|n: usize| format!("got {n}")).eval(logger_input);
+    /// let controller = map(|n: usize| n > 0).eval(controller_input);
+    ///
+    /// assert_eq!(logger.head(), "got 1");
+    /// assert!(controller.head());
+    /// ```
+    pub fn subscribe(&self) -> Subscriber<X> {
+        let mut state = self.shared.lock();
+        let cursor = Some(state.end());
+        let id = if let Some(id) = state.cursors.iter().position(Option::is_none) {
+            state.cursors[id] = cursor;
+            id
+        } else {
+            state.cursors.push(cursor);
+            state.cursors.len() - 1
+        };
+        Subscriber {
+            message: OnceCell::new(),
+            id,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Send a message to all current subscribers.
+    /// - `x` is the message.
+    ///
+    /// If the buffer is full, the policy determines whether the function blocks the current thread, drops the oldest message or returns an error with `x`.
+    /// Note that with [`Policy::Block`] the function blocks until a subscriber in another thread takes the tail.
+    ///
+    /// # Examples
+    ///
+    /// Rejecting messages as long as a subscriber lags behind:
+    ///
+    /// ```
+    /// use rspl::streams::broadcasts::{Broadcast, Full, Policy};
+    /// use rspl::streams::Stream;
+    ///
+    /// let broadcast = Broadcast::new(1, Policy::Error);
+    /// let stream = broadcast.subscribe();
+    ///
+    /// broadcast.send(1).unwrap();
+    /// assert_eq!(broadcast.send(2), Err(Full(2)));
+    ///
+    /// assert_eq!(*stream.head(), 1);
+    /// let stream = stream.tail();
+    /// broadcast.send(2).unwrap();
+    ///
+    /// assert_eq!(*stream.head(), 2);
+    /// ```
+    pub fn send(&self, x: X) -> Result<(), Full<X>> {
+        let mut state = self.shared.lock();
+        while let Some((cap, policy)) = state.bound {
+            if state.buffer.len() < cap {
+                break;
+            }
+            match policy {
+                Policy::Block => state = self.shared.wait(state),
+                Policy::DropOldest => {
+                    state.buffer.pop_front();
+                    state.offset += 1;
+                }
+                Policy::Error => return Err(Full(x)),
+            }
+        }
+        state.buffer.push_back(x);
+        state.collect();
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+}
+
+impl<X> Drop for Broadcast<X> {
+    /// Close the broadcast such that subscribers having received all messages panic on receiving instead of blocking forever.
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// [`Subscriber<X>`] abstracts subscribers of broadcasts of messages of type `X` which receive a message only on demand.
+pub struct Subscriber<X> {
+    /// message received on observing the head if it has been observed already
+    message: OnceCell<X>,
+    /// index of the cursor in the shared state
+    id: usize,
+    /// state shared with the broadcast
+    shared: Arc<Shared<X>>,
+}
+
+impl<X> Subscriber<X> {
+    /// Block the current thread until the message at the cursor is available and return a copy of it.
+    ///
+    /// If messages at the cursor have been dropped, the cursor is moved to the oldest message in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the broadcast is closed and the subscriber has received all messages.
+    fn receive(&self) -> X
+    where
+        X: Clone,
+    {
+        let mut state = self.shared.lock();
+        loop {
+            let offset = state.offset;
+            let cursor = state.cursors[self.id].as_mut().unwrap();
+            *cursor = (*cursor).max(offset);
+            let position = *cursor - offset;
+
+            if let Some(x) = state.buffer.get(position) {
+                return x.clone();
+            }
+            if state.closed {
+                // The lock is released first to not poison it for unsubscribing.
+                drop(state);
+                panic!("the broadcast is closed");
+            }
+            state = self.shared.wait(state);
+        }
+    }
+
+    /// Move the cursor past the current message and remove the messages no subscriber needs anymore.
+    fn advance(&self) {
+        let mut state = self.shared.lock();
+        *state.cursors[self.id].as_mut().unwrap() += 1;
+        state.collect();
+        self.shared.changed.notify_all();
+    }
+}
+
+impl<X> Stream<X> for Subscriber<X>
+where
+    X: Clone,
+{
+    /// Blocks the current thread until it can make a copy of the message at the cursor the head unless the message has been received already.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the broadcast is closed and the subscriber has received all messages.
+    fn head(&self) -> &X {
+        self.message.get_or_init(|| self.receive())
+    }
+
+    /// Forget the received message and move the cursor on to make `self` the tail where the message at the cursor is received and thrown away first if the head has not been observed yet.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the head has not been observed yet, the broadcast is closed and the subscriber has received all messages.
+    fn tail(mut self) -> Self {
+        if self.message.take().is_none() {
+            self.receive();
+        }
+        self.advance();
+        self
+    }
+}
+
+impl<X> Drop for Subscriber<X> {
+    /// Unsubscribe such that the subscriber does not hold back the broadcast anymore.
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.cursors[self.id] = None;
+        state.collect();
+        self.shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+
+    use std::thread;
+
+    #[test]
+    fn test_subscribe() {
+        let broadcast = Broadcast::unbounded();
+        broadcast.send(0).unwrap();
+
+        let mut stream1 = broadcast.subscribe();
+        broadcast.send(1).unwrap();
+        let mut stream2 = broadcast.subscribe();
+        broadcast.send(2).unwrap();
+
+        assert_head_eq!(stream1, 1);
+        assert_tail_starts_with!(stream1, [2]);
+        assert_head_eq!(stream2, 2);
+        assert_eq!(broadcast.shared.lock().buffer, [2]);
+
+        stream2 = stream2.tail();
+        assert_eq!(broadcast.shared.lock().buffer, [2]);
+        drop(stream1.tail());
+        assert!(broadcast.shared.lock().buffer.is_empty());
+        drop(stream2);
+    }
+
+    #[test]
+    fn test_resubscribe() {
+        let broadcast = Broadcast::<()>::unbounded();
+        let stream1 = broadcast.subscribe();
+        let stream2 = broadcast.subscribe();
+
+        drop(stream1);
+        let stream3 = broadcast.subscribe();
+        assert_eq!(stream3.id, 0);
+        assert_eq!(broadcast.shared.lock().cursors.len(), 2);
+
+        drop(stream2);
+        drop(stream3);
+    }
+
+    #[test]
+    fn test_block() {
+        let broadcast = Broadcast::new(1, Policy::Block);
+        let mut fast = broadcast.subscribe();
+        let mut slow = broadcast.subscribe();
+
+        let sender = thread::spawn(move || {
+            for n in 0..3 {
+                broadcast.send(n).unwrap();
+            }
+        });
+
+        for n in 0..3 {
+            assert_head_eq!(fast, n);
+            assert_head_eq!(slow, n);
+            fast = fast.tail();
+            slow = slow.tail();
+        }
+
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let broadcast = Broadcast::new(2, Policy::DropOldest);
+        let mut fast = broadcast.subscribe();
+        let mut slow = broadcast.subscribe();
+
+        broadcast.send(0).unwrap();
+        assert_head_eq!(fast, 0);
+        fast = fast.tail();
+        assert_head_eq!(slow, 0);
+
+        broadcast.send(1).unwrap();
+        broadcast.send(2).unwrap();
+        broadcast.send(3).unwrap();
+
+        assert_head_eq!(slow, 0);
+        assert_tail_starts_with!(slow, [2, 3]);
+        assert_head_eq!(fast, 2);
+        assert_tail_starts_with!(fast, [3]);
+    }
+
+    #[test]
+    fn test_error() {
+        let broadcast = Broadcast::new(2, Policy::Error);
+        let stream = broadcast.subscribe();
+
+        broadcast.send(0).unwrap();
+        broadcast.send(1).unwrap();
+        assert_eq!(broadcast.send(2), Err(Full(2)));
+
+        drop(stream);
+        assert_eq!(broadcast.send(2), Ok(()));
+    }
+
+    #[test]
+    fn test_tail() {
+        let broadcast = Broadcast::new(1, Policy::Error);
+        let stream = broadcast.subscribe();
+        broadcast.send(0).unwrap();
+
+        let stream = stream.tail();
+        broadcast.send(1).unwrap();
+        assert_head_eq!(stream, 1);
+        assert_eq!(broadcast.send(2), Err(Full(2)));
+
+        let stream = stream.tail();
+        broadcast.send(2).unwrap();
+        assert_head_eq!(stream, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "the broadcast is closed")]
+    fn test_closed() {
+        let broadcast = Broadcast::unbounded();
+        let stream = broadcast.subscribe();
+        broadcast.send(()).unwrap();
+        drop(broadcast);
+
+        stream.head();
+        stream.tail().head();
+    }
+
+    #[test]
+    #[should_panic(expected = "the buffer must hold at least one message")]
+    fn test_zero_cap() {
+        Broadcast::<()>::new(0, Policy::Block);
+    }
+}
@@ -0,0 +1,113 @@
+//! This module provides the standard implementation of finite streams as finite lists (the least fixpoint of `cons`ing with lazy tails).
+
+use super::FiniteStream;
+
+use crate::closures::{box_lazy, BoxedLazy, Force};
+
+/// [`FiniteList<X>`] defines lazy lists of type `X` which may end.
+pub enum FiniteList<'a, X: 'a> {
+    /// Constructing the empty list which marks the end of the stream.
+    Nil,
+    /// Constructing a new finite list by prepending a new entry to an existing (lazy) finite list.
+    Cons(X, BoxedLazy<'a, FiniteList<'a, X>>),
+}
+
+impl<'a, X> FiniteList<'a, X> {
+    /// The same as [`FiniteList::Cons`] but with boxing of `lazy_finlist` hidden to make the resulting code less verbose.
+    #[inline]
+    pub fn cons<T>(x: X, lazy_finlist: T) -> Self
+    where
+        T: FnOnce() -> Self + 'a,
+    {
+        FiniteList::Cons(x, box_lazy(lazy_finlist))
+    }
+}
+
+impl<'a, X> FiniteStream<X> for FiniteList<'a, X> {
+    /// Make the first list entry of `self` the head if there is one.
+    fn head(&self) -> Option<&X> {
+        match self {
+            Self::Nil => None,
+            Self::Cons(head, _) => Some(head),
+        }
+    }
+
+    /// Make all but the first list entry of `self` the tail where the tail of the empty list is the empty list.
+    fn tail(self) -> Self {
+        match self {
+            Self::Nil => Self::Nil,
+            Self::Cons(_, tail) => tail.force(),
+        }
+    }
+}
+
+impl<'a, X> IntoIterator for FiniteList<'a, X> {
+    type Item = X;
+    type IntoIter = IntoIter<'a, X>;
+
+    /// Turn `self` into an iterator over its entries which forces the tail of `self` only when the next entry is requested.
+    fn into_iter(self) -> IntoIter<'a, X> {
+        IntoIter {
+            lazy_finlist: Some(box_lazy(|| self)),
+        }
+    }
+}
+
+/// [`IntoIter<X>`] is the iterator over the entries of a [`FiniteList<X>`].
+pub struct IntoIter<'a, X: 'a> {
+    /// not yet forced rest of the finite list where `None` means that the list has ended
+    lazy_finlist: Option<BoxedLazy<'a, FiniteList<'a, X>>>,
+}
+
+impl<'a, X> Iterator for IntoIter<'a, X> {
+    type Item = X;
+
+    /// Force the rest of the finite list and return its first entry if there is one.
+    fn next(&mut self) -> Option<X> {
+        match self.lazy_finlist.take()?.force() {
+            FiniteList::Nil => None,
+            FiniteList::Cons(x, lazy_finlist) => {
+                self.lazy_finlist = Some(lazy_finlist);
+                Some(x)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cons() {
+        assert!(matches!(
+            FiniteList::cons((), || FiniteList::Nil),
+            FiniteList::Cons(_, _)
+        ));
+    }
+
+    #[test]
+    fn test_head() {
+        let finlist = FiniteList::cons(true, || FiniteList::Nil);
+        assert_eq!(finlist.head(), Some(&true));
+        assert_eq!(FiniteList::<bool>::Nil.head(), None);
+    }
+
+    #[test]
+    fn test_tail() {
+        let finlist = FiniteList::cons(false, || FiniteList::cons(true, || FiniteList::Nil));
+        assert_eq!(finlist.tail().head(), Some(&true));
+        assert_eq!(FiniteList::<bool>::Nil.tail().head(), None);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let finlist = FiniteList::cons(0, || FiniteList::cons(1, || FiniteList::Nil));
+
+        let mut iter = finlist.into_iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+}
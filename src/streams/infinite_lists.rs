@@ -2,15 +2,12 @@
 
 use super::Stream;
 
-use alloc::boxed::Box;
-
-/// [`Lazy<T>`] types thunks of type `T`.
-type Lazy<'a, T> = dyn FnOnce() -> T + 'a;
+use crate::closures::{box_lazy, BoxedLazy, Force};
 
 /// [`InfiniteList<X>`] defines non-well-founded lists of type `X`.
 pub enum InfiniteList<'a, X: 'a> {
     /// Constructing a new infinite list by prepending a new entry to an existing (lazy) infinite list.
-    Cons(X, Box<Lazy<'a, InfiniteList<'a, X>>>),
+    Cons(X, BoxedLazy<'a, InfiniteList<'a, X>>),
 }
 
 impl<'a, X> InfiniteList<'a, X> {
@@ -20,7 +17,7 @@ impl<'a, X> InfiniteList<'a, X> {
     where
         T: FnOnce() -> Self + 'a,
     {
-        InfiniteList::Cons(x, Box::new(lazy_inflist))
+        InfiniteList::Cons(x, box_lazy(lazy_inflist))
     }
 }
 
@@ -39,7 +36,7 @@ impl<'a, X> InfiniteList<'a, X> {
     where
         X: Copy,
     {
-        Self::Cons(x, Box::new(move || Self::constant(x)))
+        Self::cons(x, move || Self::constant(x))
     }
 }
 
@@ -54,7 +51,39 @@ impl<'a, X> Stream<X> for InfiniteList<'a, X> {
     /// Make all but the first list entry of `self` the tail.
     fn tail(self) -> Self {
         match self {
-            Self::Cons(_, tail) => tail(),
+            Self::Cons(_, tail) => tail.force(),
+        }
+    }
+}
+
+impl<'a, X> IntoIterator for InfiniteList<'a, X> {
+    type Item = X;
+    type IntoIter = IntoIter<'a, X>;
+
+    /// Turn `self` into a never ending iterator over its entries which forces the tail of `self` only when the next entry is requested.
+    fn into_iter(self) -> IntoIter<'a, X> {
+        IntoIter {
+            lazy_inflist: Some(box_lazy(|| self)),
+        }
+    }
+}
+
+/// [`IntoIter<X>`] is the never ending iterator over the entries of an [`InfiniteList<X>`].
+pub struct IntoIter<'a, X: 'a> {
+    /// not yet forced rest of the infinite list where `None` is only a transitional placeholder
+    lazy_inflist: Option<BoxedLazy<'a, InfiniteList<'a, X>>>,
+}
+
+impl<'a, X> Iterator for IntoIter<'a, X> {
+    type Item = X;
+
+    /// Force the rest of the infinite list and return its first entry.
+    fn next(&mut self) -> Option<X> {
+        match self.lazy_inflist.take()?.force() {
+            InfiniteList::Cons(x, lazy_inflist) => {
+                self.lazy_inflist = Some(lazy_inflist);
+                Some(x)
+            }
         }
     }
 }
@@ -96,4 +125,14 @@ mod tests {
         });
         assert!(inflist.tail().head());
     }
+
+    #[test]
+    fn test_into_iter() {
+        let inflist = InfiniteList::cons(0, || InfiniteList::constant(1));
+
+        let mut iter = inflist.into_iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(1));
+    }
 }
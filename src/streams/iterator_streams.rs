@@ -0,0 +1,92 @@
+//! This module provides an implementation of finite streams as adapters of iterators.
+//! The adapters are lazy in that they take the next item from the iterator only when the head is observed the first time.
+//! Taking the tail of an adapter whose head has not been observed takes the item from the iterator and throws it away right away.
+
+use super::FiniteStream;
+
+use core::cell::{OnceCell, RefCell};
+use core::iter::Fuse;
+
+/// [`IteratorStream<I>`] adapts iterators of type `I` to finite streams which end when the iterator runs out.
+pub struct IteratorStream<I: Iterator> {
+    /// item taken from the iterator on observing the head if it has been observed already
+    item: OnceCell<Option<I::Item>>,
+    /// iterator holding the remaining items
+    iter: RefCell<Fuse<I>>,
+}
+
+impl<I: Iterator> IteratorStream<I> {
+    /// Create a finite stream from anything which can be turned into an iterator.
+    /// - `xs` is what is turned into an iterator.
+    ///
+    /// # Examples
+    ///
+    /// Creating a finite stream from a vector:
+    ///
+    /// ```
+    /// use rspl::streams::iterator_streams::IteratorStream;
+    /// use rspl::streams::FiniteStream;
+    ///
+    /// let stream = IteratorStream::new(vec![1, 2, 3]);
+    ///
+    /// assert_eq!(stream.head(), Some(&1));
+    /// ```
+    pub fn new<T>(xs: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        Self {
+            item: OnceCell::new(),
+            iter: RefCell::new(xs.into_iter().fuse()),
+        }
+    }
+}
+
+impl<I: Iterator> FiniteStream<I::Item> for IteratorStream<I> {
+    /// Make the next item of the iterator the head unless it has been taken already.
+    fn head(&self) -> Option<&I::Item> {
+        self.item
+            .get_or_init(|| self.iter.borrow_mut().next())
+            .as_ref()
+    }
+
+    /// Forget the item taken from the iterator to make `self` the tail where the next item of the iterator is thrown away instead if the head has not been observed yet.
+    fn tail(mut self) -> Self {
+        if self.item.take().is_none() {
+            self.iter.get_mut().next();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let stream = IteratorStream::new([true]);
+        assert_eq!(stream.item.get(), None);
+        assert_eq!(IteratorStream::new(0..0).head(), None);
+    }
+
+    #[test]
+    fn test_head() {
+        let stream = IteratorStream::new([0, 1]);
+        assert_eq!(stream.head(), Some(&0));
+        assert_eq!(stream.head(), Some(&0));
+    }
+
+    #[test]
+    fn test_tail() {
+        let stream = IteratorStream::new([0, 1, 2]).tail();
+        assert_eq!(stream.head(), Some(&1));
+
+        let stream = stream.tail();
+        assert_eq!(stream.head(), Some(&2));
+
+        let stream = stream.tail().tail();
+        assert_eq!(stream.head(), None);
+        assert_eq!(stream.tail().head(), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! This module provides an implementation of streams as lazy receivers of messages.
+//! Here 'lazy' means that a message is only received when the head is observed the first time.
+//! So, other than with [overeager receivers](`super::overeager_receivers`), no initial placeholder message is needed.
+//!
+//! When evaluating a stream processor on a lazy receiver with [`eval`](`crate::StreamProcessor::eval`), the following rules apply:
+//! - Reading blocks until a message arrives as reading observes the head.
+//! - Writing never blocks as [`eval`](`crate::StreamProcessor::eval`) takes the tail of the input stream as late as possible, that is, only when the stream processor reads again, and taking the tail of a lazy receiver whose head has been observed does not receive anything.
+//! - Taking the tail of a lazy receiver whose head has not been observed blocks until a message arrives and throws that message away, as taking the tail of any stream throws away its first item.
+//!   In particular, a stream processor which writes before it reads the first time causes [`eval`](`crate::StreamProcessor::eval`) to do so and hence never sees the first message.
+//!   To not lose it, let the stream processor read first or evaluate it with a [`PushRunner`](`crate::runners::PushRunner`) fed by the receiver instead.
+
+use super::Stream;
+
+use crossbeam::channel::{bounded, unbounded};
+use crossbeam::channel::{Receiver, Sender};
+
+use std::cell::OnceCell;
+
+/// [`LazyReceiver<X>`] abstracts receivers of messages of type `X` which receive a message only on demand.
+pub struct LazyReceiver<X> {
+    /// message received on observing the head if it has been observed already
+    message: OnceCell<X>,
+    /// receiver of messages
+    receiver: Receiver<X>,
+}
+
+impl<X> LazyReceiver<X> {
+    /// Create a channel with a lazy receiver instead of a normal one.
+    /// - `cap` is the number of messages the channel can hold where `0` means it can hold any number of messages.
+    ///
+    /// # Examples
+    ///
+    /// Creating a stream of whatever is passed by `tx`:
+    ///
+    /// ```
+    /// use rspl::streams::lazy_receivers::LazyReceiver;
+    /// use rspl::streams::Stream;
+    ///
+    /// let (tx, stream) = LazyReceiver::channel(0);
+    /// tx.send(true).unwrap();
+    ///
+    /// assert!(stream.head());
+    /// ```
+    pub fn channel(cap: usize) -> (Sender<X>, Self) {
+        let (tx, receiver) = if cap > 0 { bounded(cap) } else { unbounded() };
+        (
+            tx,
+            Self {
+                message: OnceCell::new(),
+                receiver,
+            },
+        )
+    }
+}
+
+impl<X> Stream<X> for LazyReceiver<X> {
+    /// Blocks the current thread until it can make the received message the head unless the message has been received already.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the channel becomes disconnected.
+    fn head(&self) -> &X {
+        self.message.get_or_init(|| self.receiver.recv().unwrap())
+    }
+
+    /// Forget the received message to make `self` the tail where a message is received and thrown away instead if the head has not been observed yet.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the head has not been observed yet and the channel becomes disconnected.
+    fn tail(mut self) -> Self {
+        if self.message.take().is_none() {
+            self.receiver.recv().unwrap();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinators::map;
+    use crate::StreamProcessor;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+    use crate::enqueue;
+
+    #[test]
+    fn test_lazy_channel() {
+        let (tx, mut stream) = LazyReceiver::channel(1);
+        enqueue!(tx, [false]);
+        assert_head_eq!(stream, false);
+        enqueue!(tx, [true]);
+        assert_tail_starts_with!(stream, [true]);
+    }
+
+    #[test]
+    fn test_head() {
+        let (tx, stream) = LazyReceiver::channel(0);
+        assert!(stream.message.get().is_none());
+
+        enqueue!(tx, [1, 2]);
+        assert_head_eq!(stream, 1);
+        assert_head_eq!(stream, 1);
+    }
+
+    #[test]
+    fn test_tail() {
+        let (tx, stream) = LazyReceiver::channel(0);
+        enqueue!(tx, [0, 1, 2]);
+
+        let stream = stream.tail();
+        assert_head_eq!(stream, 1);
+        assert_head_eq!(stream.tail(), 2);
+    }
+
+    #[test]
+    fn test_eval() {
+        let (tx, stream) = LazyReceiver::channel(0);
+        enqueue!(tx, [1, 2]);
+
+        let mut result = map(|n: usize| n + 1).eval(stream);
+        assert_head_eq!(result, 2);
+        assert_tail_starts_with!(result, [3]);
+
+        let (tx, stream) = LazyReceiver::channel(0);
+        enqueue!(tx, [1, 2]);
+
+        let mut result = StreamProcessor::put(0, || map(|n: usize| n + 1)).eval(stream);
+        assert_head_eq!(result, 0);
+        assert_tail_starts_with!(result, [3]);
+    }
+}
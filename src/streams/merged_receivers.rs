@@ -0,0 +1,290 @@
+//! This module provides an implementation of streams as receivers merging the messages of several channels.
+//! Every message is tagged with the index of the channel it stems from such that one stream processor can react to several sources of input - like sensors, buttons and timers - at once.
+//!
+//! Like [lazy receivers](`super::lazy_receivers`), merged receivers only receive a message when the head is observed the first time.
+//! Which channel a message is received from if several channels have messages is determined by a [`Policy`].
+//! Channels becoming disconnected are not selected anymore.
+
+use super::Stream;
+
+use crossbeam::channel::{Receiver, Select, TryRecvError};
+
+use std::cell::{Cell, OnceCell, RefCell};
+use std::vec::Vec;
+
+/// [`Policy`] determines from which channel a merged receiver receives if several channels have messages.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Receive from a channel chosen at random among those with messages.
+    ///
+    /// Note that the choice is left to [`Select::ready`], so no order is guaranteed and the policy is randomized rather than fair.
+    Random,
+    /// Receive from the channel with the lowest index.
+    Priority,
+    /// Receive from the channel with the lowest index after the one received from last (cyclically).
+    RoundRobin,
+}
+
+/// [`MergedReceiver<'a, X>`] abstracts receivers of messages of type `X` from several channels which receive a message only on demand.
+/// The receivers are borrowed for the lifetime `'a` such that waiting for them is set up only once.
+pub struct MergedReceiver<'a, X> {
+    /// message received on observing the head tagged with its channel if it has been observed already
+    message: OnceCell<(usize, X)>,
+    /// receivers of messages
+    receivers: &'a [Receiver<X>],
+    /// whether the channel at the respective index has not become disconnected yet
+    connected: Vec<Cell<bool>>,
+    /// selection waiting for the channels not disconnected yet where the index of an operation is the index of its channel
+    select: RefCell<Select<'a>>,
+    /// index of the channel considered first by the round-robin policy
+    next: Cell<usize>,
+    /// policy choosing the channel to receive from
+    policy: Policy,
+}
+
+impl<'a, X> MergedReceiver<'a, X> {
+    /// Create a stream merging the messages of several channels.
+    /// - `receivers` are the receivers of the channels where their position is the tag of their messages.
+    /// - `policy` determines from which channel to receive if several channels have messages.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if `receivers` is empty.
+    ///
+    /// # Examples
+    ///
+    /// Merging the events of a button and a timer where the button takes precedence:
+    ///
+    /// ```
+    /// use crossbeam::channel::unbounded;
+    /// use rspl::streams::merged_receivers::{MergedReceiver, Policy};
+    /// use rspl::streams::Stream;
+    ///
+    /// let (button, button_events) = unbounded();
+    /// let (timer, timer_events) = unbounded();
+    ///
+    /// let receivers = [button_events, timer_events];
+    /// let stream = MergedReceiver::new(&receivers, Policy::Priority);
+    ///
+    /// timer.send("timeout").unwrap();
+    /// button.send("push").unwrap();
+    ///
+    /// assert_eq!(*stream.head(), (0, "push"));
+    /// assert_eq!(*stream.tail().head(), (1, "timeout"));
+    /// ```
+    pub fn new(receivers: &'a [Receiver<X>], policy: Policy) -> Self {
+        assert!(!receivers.is_empty(), "there must be at least one channel");
+
+        let mut select = Select::new();
+        for receiver in receivers {
+            select.recv(receiver);
+        }
+        Self {
+            message: OnceCell::new(),
+            receivers,
+            connected: receivers.iter().map(|_| Cell::new(true)).collect(),
+            select: RefCell::new(select),
+            next: Cell::new(0),
+            policy,
+        }
+    }
+
+    /// Block the current thread until a message can be received according to the policy.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if all channels become disconnected.
+    fn receive(&self) -> (usize, X) {
+        let n = self.receivers.len();
+        loop {
+            assert!(
+                self.connected.iter().any(Cell::get),
+                "all channels are disconnected"
+            );
+
+            let first = match self.policy {
+                Policy::Random => None,
+                Policy::Priority => Some(0),
+                Policy::RoundRobin => Some(self.next.get()),
+            };
+            if let Some(first) = first {
+                for i in (first..n).chain(0..first) {
+                    if let Some(message) = self.try_receive(i) {
+                        return message;
+                    }
+                }
+            }
+
+            let ready = self.select.borrow_mut().ready();
+
+            // With the other policies, the channels are tried in order again to not skip one which has become ready meanwhile.
+            if self.policy == Policy::Random {
+                if let Some(message) = self.try_receive(ready) {
+                    return message;
+                }
+            }
+        }
+    }
+
+    /// Receive a message from a certain channel if there is one.
+    /// - `i` is the index of the channel.
+    ///
+    /// If the channel is disconnected, it is neither considered nor waited for anymore.
+    fn try_receive(&self, i: usize) -> Option<(usize, X)> {
+        if !self.connected[i].get() {
+            return None;
+        }
+
+        match self.receivers[i].try_recv() {
+            Ok(x) => {
+                self.next.set(i + 1);
+                Some((i, x))
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.connected[i].set(false);
+                self.select.borrow_mut().remove(i);
+                None
+            }
+        }
+    }
+}
+
+impl<X> Stream<(usize, X)> for MergedReceiver<'_, X> {
+    /// Blocks the current thread until it can make the received message tagged with its channel the head unless the message has been received already.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if all channels become disconnected.
+    fn head(&self) -> &(usize, X) {
+        self.message.get_or_init(|| self.receive())
+    }
+
+    /// Forget the received message to make `self` the tail where a message is received and thrown away instead if the head has not been observed yet.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if the head has not been observed yet and all channels become disconnected.
+    fn tail(mut self) -> Self {
+        if self.message.take().is_none() {
+            self.receive();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::unbounded as channel;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+    use crate::enqueue;
+
+    use std::thread;
+
+    #[test]
+    fn test_priority() {
+        let (tx0, rx0) = channel();
+        let (tx1, rx1) = channel();
+        enqueue!(tx1, [10, 11]);
+        enqueue!(tx0, [0, 1]);
+
+        let receivers = [rx0, rx1];
+        let mut stream = MergedReceiver::new(&receivers, Policy::Priority);
+        assert_head_eq!(stream, (0, 0));
+        assert_tail_starts_with!(stream, [(0, 1), (1, 10), (1, 11)]);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let (tx0, rx0) = channel();
+        let (tx1, rx1) = channel();
+        let (tx2, rx2) = channel();
+        enqueue!(tx0, [0, 1]);
+        enqueue!(tx1, [10]);
+        enqueue!(tx2, [20, 21]);
+
+        let receivers = [rx0, rx1, rx2];
+        let mut stream = MergedReceiver::new(&receivers, Policy::RoundRobin);
+        assert_head_eq!(stream, (0, 0));
+        assert_tail_starts_with!(stream, [(1, 10), (2, 20), (0, 1), (2, 21)]);
+    }
+
+    #[test]
+    fn test_random() {
+        let (tx0, rx0) = channel();
+        let (tx1, rx1) = channel();
+        let txs = [tx0, tx1];
+        for (i, tx) in txs.iter().enumerate() {
+            enqueue!(tx, [i]);
+        }
+
+        // Both channels always have a message, so every draw picks either with probability one half.
+        let receivers = [rx0, rx1];
+        let mut stream = MergedReceiver::new(&receivers, Policy::Random);
+        let mut counts = [0, 0];
+        for _ in 0..64 {
+            let (i, x) = *stream.head();
+            assert_eq!(i, x);
+            counts[i] += 1;
+            enqueue!(txs[i], [i]);
+            stream = stream.tail();
+        }
+        assert!(counts[0] > 0 && counts[1] > 0);
+    }
+
+    #[test]
+    fn test_random_only_ready() {
+        let (tx0, rx0) = channel();
+        let (_tx1, rx1) = channel();
+        enqueue!(tx0, [0, 1, 2]);
+
+        let receivers = [rx0, rx1];
+        let mut stream = MergedReceiver::new(&receivers, Policy::Random);
+        assert_head_eq!(stream, (0, 0));
+        assert_tail_starts_with!(stream, [(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_tail() {
+        let (tx, rx) = channel();
+        enqueue!(tx, [0, 1, 2]);
+
+        let receivers = [rx];
+        let stream = MergedReceiver::new(&receivers, Policy::Priority).tail();
+        assert_head_eq!(stream, (0, 1));
+        assert_head_eq!(stream.tail(), (0, 2));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let (tx0, rx0) = channel();
+        let (tx1, rx1) = channel();
+        drop(tx0);
+
+        let receivers = [rx0, rx1];
+        let stream = MergedReceiver::new(&receivers, Policy::RoundRobin);
+
+        let sender = thread::spawn(move || enqueue!(tx1, [true]));
+        assert_head_eq!(stream, (1, true));
+        sender.join().unwrap();
+
+        assert!(!stream.connected[0].get());
+        assert!(stream.connected[1].get());
+    }
+
+    #[test]
+    #[should_panic(expected = "all channels are disconnected")]
+    fn test_all_disconnected() {
+        let (_, rx) = channel::<()>();
+        MergedReceiver::new(&[rx], Policy::Random).head();
+    }
+
+    #[test]
+    #[should_panic(expected = "there must be at least one channel")]
+    fn test_empty() {
+        MergedReceiver::<()>::new(&[], Policy::Random);
+    }
+}
@@ -1,11 +1,29 @@
 //! This module defines streams of some type intensionally by means of a trait.
 //! Additionally, the module declares submodules with implementations of the trait.
 
+#[cfg(feature = "std")]
+pub mod broadcasts;
+
+#[cfg(feature = "alloc")]
+pub mod finite_lists;
+#[cfg(feature = "alloc")]
 pub mod infinite_lists;
+pub mod iterator_streams;
+
+#[cfg(feature = "std")]
+pub mod lazy_receivers;
+
+#[cfg(feature = "std")]
+pub mod merged_receivers;
 
 #[cfg(feature = "std")]
 pub mod overeager_receivers;
 
+#[cfg(feature = "alloc")]
+pub mod shared_lists;
+
+use core::task::Poll;
+
 /// A characterization of streams of some type `X`: a stream of `X` is an object from which one can observe something of type `X` (the head of the stream) or another stream of `X` (the tail of the stream).
 pub trait Stream<X> {
     /// Return a reference to the first item of `self`.
@@ -14,6 +32,90 @@ pub trait Stream<X> {
     fn tail(self) -> Self;
 }
 
+/// A characterization of finite streams of some type `X`: a finite stream of `X` is an object from which one can observe whether it has ended and, if not, something of type `X` (the head of the stream) or another finite stream of `X` (the tail of the stream).
+pub trait FiniteStream<X> {
+    /// Return a reference to the first item of `self` or `None` if `self` has ended.
+    fn head(&self) -> Option<&X>;
+    /// Throw away the first item of `self` and return what is left where the tail of an ended stream is ended again.
+    fn tail(self) -> Self;
+
+    /// Turn `self` into a finite stream which explicitly marks its end (see [`Terminated`]).
+    ///
+    /// # Examples
+    ///
+    /// Marking the end of a stream with one element:
+    ///
+    /// ```
+    /// use rspl::streams::finite_lists::FiniteList;
+    /// use rspl::streams::FiniteStream;
+    ///
+    /// let stream = FiniteList::cons(true, || FiniteList::Nil).terminated();
+    ///
+    /// assert_eq!(stream.tail().head(), Some(&None));
+    /// ```
+    fn terminated(self) -> Terminated<X, Self>
+    where
+        X: Clone,
+        Self: Sized,
+    {
+        Terminated {
+            message: Some(self.head().cloned()),
+            stream: self,
+        }
+    }
+}
+
+/// [`Terminated<X, S>`] wraps finite streams of type `S` such that every item `x` becomes `Some(x)` and the end is marked by a `None` before the stream actually ends.
+/// This allows stream processors on `Option<X>` to react to the end of the input, for example, to flush buffered state.
+pub struct Terminated<X, S> {
+    /// current item where `None` means that the end has been marked already
+    message: Option<Option<X>>,
+    /// wrapped finite stream
+    stream: S,
+}
+
+impl<X, S> FiniteStream<Option<X>> for Terminated<X, S>
+where
+    X: Clone,
+    S: FiniteStream<X>,
+{
+    /// Make the current item of `self` the head.
+    fn head(&self) -> Option<&Option<X>> {
+        self.message.as_ref()
+    }
+
+    /// Advance the wrapped stream unless the end has been marked already.
+    fn tail(mut self) -> Self {
+        if let Some(Some(_)) = self.message {
+            self.stream = self.stream.tail();
+            self.message = Some(self.stream.head().cloned());
+        } else {
+            self.message = None;
+        }
+        self
+    }
+}
+
+/// A characterization of sources of some type `X` which are polled for input: polling a source either yields its next item of type `X`, tells that there is no item yet or tells that the source has ended.
+///
+/// Other than with [`Stream`] and [`FiniteStream`] observing a source this way never blocks.
+pub trait PollStream<X> {
+    /// Return `Poll::Ready(Some(x))` if `x` is the next item of `self`, `Poll::Pending` if there is no item yet and `Poll::Ready(None)` if `self` has ended.
+    fn poll(&mut self) -> Poll<Option<X>>;
+}
+
+#[cfg(feature = "std")]
+impl<X> PollStream<X> for crossbeam::channel::Receiver<X> {
+    /// Receive the next message if there is one in the channel already where the end is reached when the channel is empty and disconnected.
+    fn poll(&mut self) -> Poll<Option<X>> {
+        match self.try_recv() {
+            Ok(x) => Poll::Ready(Some(x)),
+            Err(crossbeam::channel::TryRecvError::Empty) => Poll::Pending,
+            Err(crossbeam::channel::TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
 /// Print a specified number of elements from some provided stream returning the not printed part.
 /// - `stream` is the stream to be printed.
 /// - `n` is the number of elements to be printed.
@@ -32,7 +134,7 @@ pub trait Stream<X> {
 /// use rspl::streams::infinite_lists::InfiniteList;
 ///
 /// fn ascending<'a>(n: usize) -> InfiniteList<'a, usize> {
-///     InfiniteList::Cons(n, Box::new(move || ascending(n + 1)))
+///     InfiniteList::cons(n, move || ascending(n + 1))
 /// }
 ///
 /// rspl::streams::print(ascending(0), 5);
@@ -53,9 +155,11 @@ where
 
 #[cfg(test)]
 mod tests {
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     use super::*;
 
+    #[cfg(feature = "alloc")]
+    use finite_lists::FiniteList;
     #[cfg(feature = "std")]
     use infinite_lists::InfiniteList;
 
@@ -88,4 +192,34 @@ mod tests {
         let stream = print(stream, 2);
         assert!(stream.head());
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_poll() {
+        let (tx, mut rx) = crossbeam::channel::unbounded();
+        assert_eq!(rx.poll(), Poll::Pending);
+
+        tx.send(true).unwrap();
+        assert_eq!(rx.poll(), Poll::Ready(Some(true)));
+        assert_eq!(rx.poll(), Poll::Pending);
+
+        tx.send(false).unwrap();
+        drop(tx);
+        assert_eq!(rx.poll(), Poll::Ready(Some(false)));
+        assert_eq!(rx.poll(), Poll::Ready(None));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_terminated() {
+        let stream = FiniteList::cons(1, || FiniteList::Nil).terminated();
+        assert_eq!(stream.head(), Some(&Some(1)));
+
+        let stream = stream.tail();
+        assert_eq!(stream.head(), Some(&None));
+
+        let stream = stream.tail();
+        assert_eq!(stream.head(), None);
+        assert_eq!(stream.tail().head(), None);
+    }
 }
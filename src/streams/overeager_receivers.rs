@@ -1,10 +1,17 @@
 //! This module provides an implementation of streams as overeager receivers of messages.
 //! Here 'overeager' means that one message is always received in advance.
+//!
+//! By default, taking the tail of an overeager receiver blocks until the next message arrives and panics if the channel becomes disconnected.
+//! For more control, there are the non-blocking [`try_tail`](`OvereagerReceiver::try_tail`) and the time-bounded [`tail_timeout`](`OvereagerReceiver::tail_timeout`).
+//! Moreover, overeager receivers can be turned into disconnect-aware ones which either end ([`FiniteReceiver`]) or repeat a sentinel message ([`SentinelReceiver`]) when the channel becomes disconnected.
+//! As [`try_tail`](`OvereagerReceiver::try_tail`) and [`tail_timeout`](`OvereagerReceiver::tail_timeout`) cannot be used by [`eval`](`crate::StreamProcessor::eval`), overeager receivers can also be turned into disconnect-aware ones which additionally receive a timeout message when no message arrives in time ([`TimeoutReceiver`]).
 
-use super::Stream;
+use super::{FiniteStream, Stream};
 
 use crossbeam::channel::{bounded, unbounded};
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+
+use std::time::Duration;
 
 /// [`OvereagerReceiver<X>`] abstracts receivers of messages of type `X` which always buffer one message.
 pub struct OvereagerReceiver<X> {
@@ -30,6 +37,177 @@ impl<X> OvereagerReceiver<X> {
         let (tx, receiver) = if cap > 0 { bounded(cap) } else { unbounded() };
         (tx, Self { message, receiver })
     }
+
+    /// Make `self` with an updated message buffer the tail if there is a message in the channel already.
+    ///
+    /// Other than [`tail`](`Stream::tail`) the method never blocks.
+    /// Instead, if there is no message yet or the channel is disconnected, `self` is returned unchanged together with the reason.
+    ///
+    /// # Examples
+    ///
+    /// Trying to take the tail before and after sending a message:
+    ///
+    /// ```
+    /// use crossbeam::channel::TryRecvError;
+    /// use rspl::streams::overeager_receivers::OvereagerReceiver;
+    /// use rspl::streams::Stream;
+    ///
+    /// let (tx, stream) = OvereagerReceiver::channel(0, false);
+    ///
+    /// let Err((stream, TryRecvError::Empty)) = stream.try_tail() else {
+    ///     panic!("the channel is not empty");
+    /// };
+    ///
+    /// tx.send(true).unwrap();
+    /// let Ok(stream) = stream.try_tail() else {
+    ///     panic!("the channel is empty");
+    /// };
+    ///
+    /// assert!(stream.head());
+    /// ```
+    pub fn try_tail(mut self) -> Result<Self, (Self, TryRecvError)> {
+        match self.receiver.try_recv() {
+            Ok(message) => {
+                self.message = message;
+                Ok(self)
+            }
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Block the current thread for at most some time until it can make `self` with an updated message buffer the tail.
+    /// - `timeout` is the maximal time to wait for a message.
+    ///
+    /// If no message arrives in time or the channel is disconnected, `self` is returned unchanged together with the reason.
+    /// Then, for example, a driver holding a [`Sender`] of the channel can inject a timeout event by sending it.
+    ///
+    /// # Examples
+    ///
+    /// Waiting for a message which is never sent:
+    ///
+    /// ```
+    /// use crossbeam::channel::RecvTimeoutError;
+    /// use rspl::streams::overeager_receivers::OvereagerReceiver;
+    ///
+    /// use std::time::Duration;
+    ///
+    /// let (_tx, stream) = OvereagerReceiver::channel(0, false);
+    ///
+    /// assert!(matches!(
+    ///     stream.tail_timeout(Duration::from_millis(10)),
+    ///     Err((_, RecvTimeoutError::Timeout))
+    /// ));
+    /// ```
+    pub fn tail_timeout(mut self, timeout: Duration) -> Result<Self, (Self, RecvTimeoutError)> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(message) => {
+                self.message = message;
+                Ok(self)
+            }
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Turn `self` into a stream which receives a timeout message if no message arrives for some time and repeats a sentinel message when the channel becomes disconnected.
+    /// - `timeout` is the maximal time to wait for a message.
+    /// - `event` is the timeout message.
+    /// - `sentinel` is the sentinel message.
+    ///
+    /// Other than with [`tail_timeout`](`OvereagerReceiver::tail_timeout`) the result is a [`Stream`] again.
+    /// So, stream processors evaluated on it with [`eval`](`crate::StreamProcessor::eval`) can react to timeouts like to any other message.
+    ///
+    /// # Examples
+    ///
+    /// Receiving a `Tick`-event when nothing happens:
+    ///
+    /// ```
+    /// use rspl::streams::overeager_receivers::OvereagerReceiver;
+    /// use rspl::streams::Stream;
+    ///
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// enum Event {
+    ///     Push,
+    ///     Tick,
+    ///     Exit,
+    /// }
+    ///
+    /// let (tx, stream) = OvereagerReceiver::channel(0, Event::Push);
+    ///
+    /// let stream = stream
+    ///     .with_timeout(Duration::from_millis(10), Event::Tick, Event::Exit)
+    ///     .tail();
+    /// assert_eq!(*stream.head(), Event::Tick);
+    ///
+    /// drop(tx);
+    /// assert_eq!(*stream.tail().head(), Event::Exit);
+    /// ```
+    pub fn with_timeout(self, timeout: Duration, event: X, sentinel: X) -> TimeoutReceiver<X> {
+        TimeoutReceiver {
+            message: self.message,
+            timeout,
+            event,
+            sentinel,
+            receiver: self.receiver,
+        }
+    }
+
+    /// Turn `self` into a finite stream which ends when the channel becomes disconnected.
+    ///
+    /// # Examples
+    ///
+    /// Ending a stream by dropping the sender:
+    ///
+    /// ```
+    /// use rspl::streams::overeager_receivers::OvereagerReceiver;
+    /// use rspl::streams::FiniteStream;
+    ///
+    /// let (tx, stream) = OvereagerReceiver::channel(0, 0);
+    /// tx.send(1).unwrap();
+    /// drop(tx);
+    ///
+    /// let stream = stream.into_finite().tail();
+    /// assert_eq!(stream.head(), Some(&1));
+    /// assert_eq!(stream.tail().head(), None);
+    /// ```
+    pub fn into_finite(self) -> FiniteReceiver<X> {
+        FiniteReceiver {
+            message: Some(self.message),
+            receiver: self.receiver,
+        }
+    }
+
+    /// Turn `self` into a stream which repeats a sentinel message when the channel becomes disconnected.
+    /// - `sentinel` is the sentinel message.
+    ///
+    /// # Examples
+    ///
+    /// Shutting down a machine with an `Exit`-event when the sender is dropped:
+    ///
+    /// ```
+    /// use rspl::streams::overeager_receivers::OvereagerReceiver;
+    /// use rspl::streams::Stream;
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// enum Event {
+    ///     Push,
+    ///     Exit,
+    /// }
+    ///
+    /// let (tx, stream) = OvereagerReceiver::channel(0, Event::Push);
+    /// drop(tx);
+    ///
+    /// let stream = stream.with_sentinel(Event::Exit).tail();
+    /// assert_eq!(*stream.head(), Event::Exit);
+    /// ```
+    pub fn with_sentinel(self, sentinel: X) -> SentinelReceiver<X> {
+        SentinelReceiver {
+            message: self.message,
+            sentinel,
+            receiver: self.receiver,
+        }
+    }
 }
 
 impl<X> Stream<X> for OvereagerReceiver<X> {
@@ -49,9 +227,90 @@ impl<X> Stream<X> for OvereagerReceiver<X> {
     }
 }
 
+/// [`FiniteReceiver<X>`] abstracts receivers of messages of type `X` which always buffer one message and end when the channel becomes disconnected.
+pub struct FiniteReceiver<X> {
+    /// overeagerly received message or `None` if the channel has become disconnected
+    message: Option<X>,
+    /// receiver of messages
+    receiver: Receiver<X>,
+}
+
+impl<X> FiniteStream<X> for FiniteReceiver<X> {
+    /// Make the message buffer of `self` the head.
+    fn head(&self) -> Option<&X> {
+        self.message.as_ref()
+    }
+
+    /// Blocks the current thread until it can make `self` with an updated message buffer the tail or the channel becomes disconnected.
+    fn tail(mut self) -> Self {
+        if self.message.is_some() {
+            self.message = self.receiver.recv().ok();
+        }
+        self
+    }
+}
+
+/// [`SentinelReceiver<X>`] abstracts receivers of messages of type `X` which always buffer one message and repeat a sentinel message when the channel becomes disconnected.
+pub struct SentinelReceiver<X> {
+    /// overeagerly received message
+    message: X,
+    /// message received from a disconnected channel
+    sentinel: X,
+    /// receiver of messages
+    receiver: Receiver<X>,
+}
+
+impl<X: Clone> Stream<X> for SentinelReceiver<X> {
+    /// Make the message buffer of `self` the head.
+    fn head(&self) -> &X {
+        &self.message
+    }
+
+    /// Blocks the current thread until it can make `self` with an updated message buffer the tail where the sentinel is received if the channel is disconnected.
+    fn tail(mut self) -> Self {
+        self.message = self
+            .receiver
+            .recv()
+            .unwrap_or_else(|_| self.sentinel.clone());
+        self
+    }
+}
+
+/// [`TimeoutReceiver<X>`] abstracts receivers of messages of type `X` which always buffer one message, receive a timeout message when no message arrives in time and repeat a sentinel message when the channel becomes disconnected.
+pub struct TimeoutReceiver<X> {
+    /// overeagerly received message
+    message: X,
+    /// maximal time to wait for a message
+    timeout: Duration,
+    /// message received if no message arrives in time
+    event: X,
+    /// message received from a disconnected channel
+    sentinel: X,
+    /// receiver of messages
+    receiver: Receiver<X>,
+}
+
+impl<X: Clone> Stream<X> for TimeoutReceiver<X> {
+    /// Make the message buffer of `self` the head.
+    fn head(&self) -> &X {
+        &self.message
+    }
+
+    /// Blocks the current thread for at most the timeout until it can make `self` with an updated message buffer the tail where the timeout message is received if no message arrives in time and the sentinel is received if the channel is disconnected.
+    fn tail(mut self) -> Self {
+        self.message = match self.receiver.recv_timeout(self.timeout) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => self.event.clone(),
+            Err(RecvTimeoutError::Disconnected) => self.sentinel.clone(),
+        };
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::combinators::map;
     use crossbeam::channel::unbounded as channel;
 
     use crate::assert_head_eq;
@@ -94,4 +353,86 @@ mod tests {
         enqueue!(tx, [true]);
         assert!(stream.tail().head());
     }
+
+    #[test]
+    fn test_try_tail() {
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+
+        let Err((stream, TryRecvError::Empty)) = stream.try_tail() else {
+            panic!("message received from empty channel");
+        };
+
+        enqueue!(tx, [1]);
+        let Ok(stream) = stream.try_tail() else {
+            panic!("no message received from non-empty channel");
+        };
+        assert_head_eq!(stream, 1);
+
+        drop(tx);
+        let Err((stream, TryRecvError::Disconnected)) = stream.try_tail() else {
+            panic!("message received from disconnected channel");
+        };
+        assert_head_eq!(stream, 1);
+    }
+
+    #[test]
+    fn test_tail_timeout() {
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+
+        let Err((stream, RecvTimeoutError::Timeout)) = stream.tail_timeout(Duration::ZERO) else {
+            panic!("message received from empty channel");
+        };
+
+        enqueue!(tx, [1]);
+        let Ok(stream) = stream.tail_timeout(Duration::from_secs(1)) else {
+            panic!("no message received from non-empty channel");
+        };
+        assert_head_eq!(stream, 1);
+
+        drop(tx);
+        let Err((_, RecvTimeoutError::Disconnected)) = stream.tail_timeout(Duration::ZERO) else {
+            panic!("message received from disconnected channel");
+        };
+    }
+
+    #[test]
+    fn test_timeout_receiver() {
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+        enqueue!(tx, [1]);
+
+        let stream = stream.with_timeout(Duration::ZERO, -1, -2);
+        let mut result = map(|n: isize| n * 2).eval(stream);
+        assert_head_eq!(result, 0);
+        assert_tail_starts_with!(result, [2, -2]);
+
+        enqueue!(tx, [2]);
+        assert_tail_starts_with!(result, [4, -2]);
+
+        drop(tx);
+        assert_tail_starts_with!(result, [-4, -4]);
+    }
+
+    #[test]
+    fn test_finite_receiver() {
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+        enqueue!(tx, [1, 2]);
+        drop(tx);
+
+        let mut stream = stream.into_finite();
+        for n in [Some(&0), Some(&1), Some(&2), None, None] {
+            assert_eq!(stream.head(), n);
+            stream = stream.tail();
+        }
+    }
+
+    #[test]
+    fn test_sentinel_receiver() {
+        let (tx, stream) = OvereagerReceiver::channel(0, 0);
+        enqueue!(tx, [1]);
+        drop(tx);
+
+        let mut stream = stream.with_sentinel(-1);
+        assert_head_eq!(stream, 0);
+        assert_tail_starts_with!(stream, [1, -1, -1]);
+    }
 }
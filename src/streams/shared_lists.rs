@@ -0,0 +1,211 @@
+//! This module provides an implementation of streams as shared infinite lists.
+//! Other than with [infinite lists](`super::infinite_lists`), the tail of a shared list is forced at most once and then cached.
+//! Hence shared lists can be cloned cheaply to let several readers traverse the same list - like the result of [`eval`](`crate::StreamProcessor::eval`) - independently.
+//!
+//! Note that shared lists are single-threaded on purpose: the clones share their nodes by reference counting without atomics and the tails are lazy closures which need not be sendable.
+//! To let readers in several threads traverse the same input, use a broadcast from the `broadcasts` module instead.
+
+use super::infinite_lists::InfiniteList;
+use super::Stream;
+
+use crate::closures::{box_lazy, BoxedLazy, Force};
+
+use alloc::rc::Rc;
+use core::cell::{Cell, OnceCell};
+
+/// [`Node<X>`] is a list entry of type `X` of a shared list together with the rest of the list.
+struct Node<'a, X: 'a> {
+    /// list entry
+    head: X,
+    /// rest of the list if it has been forced already
+    tail: OnceCell<SharedList<'a, X>>,
+    /// rest of the list if it has not been forced yet
+    lazy_tail: Cell<Option<BoxedLazy<'a, SharedList<'a, X>>>>,
+}
+
+/// [`SharedList<X>`] defines non-well-founded lists of type `X` whose tails are memoized and shared between clones.
+pub struct SharedList<'a, X: 'a>(Rc<Node<'a, X>>);
+
+impl<'a, X> SharedList<'a, X> {
+    /// Construct a new shared list by prepending a new entry to an existing (lazy) shared list.
+    /// - `x` is the new entry.
+    /// - `lazy_shared_list` is the existing shared list which is forced at most once.
+    ///
+    /// # Examples
+    ///
+    /// Creating a shared list whose tail is only computed once:
+    ///
+    /// ```
+    /// use rspl::streams::shared_lists::SharedList;
+    /// use rspl::streams::Stream;
+    ///
+    /// fn naturals<'a>(n: usize) -> SharedList<'a, usize> {
+    ///     SharedList::cons(n, move || naturals(n + 1))
+    /// }
+    ///
+    /// let reader1 = naturals(0);
+    /// let reader2 = reader1.clone();
+    ///
+    /// assert_eq!(*reader1.tail().head(), 1);
+    /// assert_eq!(*reader2.tail().head(), 1);
+    /// ```
+    pub fn cons<T>(x: X, lazy_shared_list: T) -> Self
+    where
+        T: FnOnce() -> Self + 'a,
+    {
+        Self(Rc::new(Node {
+            head: x,
+            tail: OnceCell::new(),
+            lazy_tail: Cell::new(Some(box_lazy(lazy_shared_list))),
+        }))
+    }
+}
+
+impl<X> Clone for SharedList<'_, X> {
+    /// Share `self` such that the clone reads the same entries without forcing any tail again.
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<'a, X> From<InfiniteList<'a, X>> for SharedList<'a, X> {
+    /// Turn an infinite list into a shared list with the same entries which forces the tail of the infinite list only when its own tail is forced.
+    ///
+    /// # Examples
+    ///
+    /// Letting two readers traverse the output of a stream processor:
+    ///
+    /// ```
+    /// use rspl::combinators::map;
+    /// use rspl::streams::infinite_lists::InfiniteList;
+    /// use rspl::streams::shared_lists::SharedList;
+    /// use rspl::streams::Stream;
+    ///
+    /// let output = map(|n: usize| n * 2).eval(InfiniteList::constant(1));
+    ///
+    /// let reader1 = SharedList::from(output);
+    /// let reader2 = reader1.clone();
+    ///
+    /// assert_eq!(*reader1.tail().head(), 2);
+    /// assert_eq!(*reader2.head(), 2);
+    /// ```
+    fn from(inflist: InfiniteList<'a, X>) -> Self {
+        match inflist {
+            InfiniteList::Cons(x, lazy_inflist) => {
+                Self::cons(x, move || Self::from(lazy_inflist.force()))
+            }
+        }
+    }
+}
+
+impl<X> Stream<X> for SharedList<'_, X> {
+    /// Make the first list entry of `self` the head.
+    fn head(&self) -> &X {
+        &self.0.head
+    }
+
+    /// Make all but the first list entry of `self` the tail where the tail is forced only if no clone of `self` has forced it already.
+    ///
+    /// # Panics
+    ///
+    /// A panic is caused if
+    /// - forcing the tail panics.
+    /// - forcing the tail has panicked before and `self` or a clone of it is asked for the tail again.
+    /// - forcing the tail asks `self` or a clone of it for the tail.
+    fn tail(self) -> Self {
+        let node = &self.0;
+        node.tail
+            .get_or_init(|| {
+                node.lazy_tail
+                    .take()
+                    .expect(
+                        "the tail is forced again after forcing it panicked or while forcing it",
+                    )
+                    .force()
+            })
+            .clone()
+    }
+}
+
+impl<X> Drop for SharedList<'_, X> {
+    /// Drop the forced entries not shared anymore one after another instead of recursively to not overflow the stack on long lists.
+    fn drop(&mut self) {
+        let mut tail = Rc::get_mut(&mut self.0).and_then(|node| node.tail.take());
+        while let Some(mut shared_list) = tail {
+            tail = Rc::get_mut(&mut shared_list.0).and_then(|node| node.tail.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_head_eq;
+    use crate::assert_tail_starts_with;
+
+    fn counting<'a>(n: usize, forced: Rc<Cell<usize>>) -> SharedList<'a, usize> {
+        SharedList::cons(n, move || {
+            forced.set(forced.get() + 1);
+            counting(n + 1, forced)
+        })
+    }
+
+    #[test]
+    fn test_cons() {
+        let shared_list = SharedList::cons(true, || SharedList::cons(false, || unreachable!()));
+        assert!(shared_list.0.tail.get().is_none());
+    }
+
+    #[test]
+    fn test_head() {
+        let shared_list = counting(0, Rc::new(Cell::new(0)));
+        assert_head_eq!(shared_list, 0);
+        assert_head_eq!(shared_list.clone(), 0);
+    }
+
+    #[test]
+    fn test_tail() {
+        let forced = Rc::new(Cell::new(0));
+        let mut shared_list = counting(0, forced.clone());
+        let mut clone = shared_list.clone();
+
+        assert_tail_starts_with!(shared_list, [1, 2, 3]);
+        assert_eq!(forced.get(), 3);
+        assert_tail_starts_with!(clone, [1, 2, 3, 4]);
+        assert_eq!(forced.get(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "the tail is forced again after forcing it panicked")]
+    fn test_tail_after_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let shared_list = SharedList::cons(0, || panic!("forcing failed"));
+        let clone = shared_list.clone();
+
+        assert!(catch_unwind(AssertUnwindSafe(|| shared_list.tail())).is_err());
+        clone.tail();
+    }
+
+    #[test]
+    fn test_from() {
+        let mut shared_list = SharedList::from(InfiniteList::cons(0, || InfiniteList::constant(1)));
+        assert_head_eq!(shared_list, 0);
+        assert_tail_starts_with!(shared_list, [1, 1]);
+    }
+
+    #[test]
+    fn test_drop() {
+        let shared_list = counting(0, Rc::new(Cell::new(0)));
+
+        let mut reader = shared_list.clone();
+        for _ in 0..1_000_000 {
+            reader = reader.tail();
+        }
+        drop(reader);
+
+        drop(shared_list);
+    }
+}
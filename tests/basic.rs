@@ -12,7 +12,7 @@ fn test_basic() {
         x
     }
 
-    let is_zero = |n: usize| n == 0;
+    let is_zero = StreamProcessor::get(|n: usize| StreamProcessor::Done(n == 0));
 
     // a silly stream processor
     let sp_aux = StreamProcessor::get(|n1: usize| {
@@ -20,7 +20,7 @@ fn test_basic() {
             StreamProcessor::put(n2, move || StreamProcessor::put(n1, || map(id)))
         })
     });
-    let sp = bind(map(is_zero), |b| {
+    let sp = bind(is_zero, |b| {
         if b {
             alternate(sp_aux, map(|n| n + 1))
         } else {
@@ -32,7 +32,7 @@ fn test_basic() {
     let (tx, stream) = OvereagerReceiver::channel(0, 0);
     let input_simulator = thread::spawn(move || {
         fn ascending<'a>(n: usize) -> InfiniteList<'a, usize> {
-            InfiniteList::Cons(n, Box::new(move || ascending(n + 1)))
+            InfiniteList::cons(n, move || ascending(n + 1))
         }
 
         let mut stream = ascending(1);
@@ -1,28 +1,80 @@
 use rspl::combinators::{compose, filter, map};
+use rspl::fusion;
+use rspl::fusion::Pipeline;
+use rspl::streams::infinite_lists::InfiniteList;
 use rspl::streams::overeager_receivers::OvereagerReceiver;
-use rspl::streams::print;
+use rspl::streams::{print, Stream};
+use rspl::StreamProcessor;
 
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
 use std::thread;
 
-#[test]
-#[ignore]
-fn test_load() {
-    const N: usize = 10;
+const N: usize = 10;
 
-    const fn factorial(mut n: usize) -> usize {
-        let mut acc = n;
-        while n > 1 {
-            acc *= n - 1;
-            n -= 1;
-        }
-        acc
+const OUTPUTS: usize = 100_000;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// The fusion gain is checked by counting allocations instead of measuring time to not depend on
+// the machine the tests run on. Only the allocations of the current thread are counted such that
+// tests running in parallel do not interfere.
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const fn factorial(mut n: usize) -> usize {
+    let mut acc = n;
+    while n > 1 {
+        acc *= n - 1;
+        n -= 1;
     }
+    acc
+}
 
-    let is_even = |n: &usize| *n % 2 == 0;
-    let plus_one = |n: usize| n + 1;
+#[allow(clippy::manual_is_multiple_of)] // `is_multiple_of` needs Rust 1.87
+const fn is_even(n: &usize) -> bool {
+    *n % 2 == 0
+}
 
-    let sp = compose(compose(filter(is_even), map(factorial)), map(plus_one));
+const fn plus_one(n: usize) -> usize {
+    n + 1
+}
 
+fn unfused<'a>() -> StreamProcessor<'a, usize, usize> {
+    compose(compose(filter(is_even), map(factorial)), map(plus_one))
+}
+
+fn fused() -> impl Pipeline<usize, Output = usize> {
+    fusion::compose(
+        fusion::compose(fusion::filter(is_even), fusion::map(factorial)),
+        fusion::map(plus_one),
+    )
+}
+
+fn inputs<'a>(n: usize) -> InfiniteList<'a, usize> {
+    InfiniteList::cons(n % N, move || inputs(n + 1))
+}
+
+fn load<S, T>(eval: T)
+where
+    S: Stream<usize>,
+    T: FnOnce(OvereagerReceiver<usize>) -> S,
+{
     let (tx, stream) = OvereagerReceiver::channel(0, 0);
     let input_simulator = thread::spawn(move || {
         for _ in 0..factorial(N) {
@@ -32,7 +84,7 @@ fn test_load() {
         }
     });
 
-    let result = sp.eval(stream);
+    let result = eval(stream);
 
     let rest = print(result, factorial(N));
 
@@ -40,3 +92,42 @@ fn test_load() {
 
     print(rest, factorial(N));
 }
+
+fn measure<S: Stream<usize>>(eval: impl FnOnce() -> S) -> (usize, usize) {
+    let start = ALLOCATIONS.with(Cell::get);
+    let mut result = eval();
+    let mut sum = 0;
+    for _ in 0..OUTPUTS {
+        sum += *result.head();
+        result = result.tail();
+    }
+    (ALLOCATIONS.with(Cell::get) - start, sum)
+}
+
+#[test]
+#[ignore]
+fn test_load() {
+    load(|stream| unfused().eval(stream));
+}
+
+#[test]
+#[ignore]
+fn test_load_fused() {
+    load(|stream| fused().eval(stream));
+}
+
+#[test]
+fn test_load_fused_output() {
+    let (_, unfused_sum) = measure(|| unfused().eval(inputs(0)));
+    let (_, fused_sum) = measure(|| fused().eval(inputs(0)));
+
+    assert_eq!(unfused_sum, fused_sum);
+}
+
+#[test]
+fn test_load_fused_allocations() {
+    let (unfused, _) = measure(|| unfused().eval(inputs(0)));
+    let (fused, _) = measure(|| fused().eval(inputs(0)));
+
+    assert!(2 * fused < unfused);
+}